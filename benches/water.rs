@@ -0,0 +1,98 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use duck::water::Water;
+use nalgebra::Vector2;
+
+const SIZES: [usize; 3] = [256, 512, 1024];
+const WARMUP_STEPS: usize = 10;
+
+fn disturbed_water(size: usize, parallel: bool) -> Water {
+    let mut water = Water::new(size, size, Vector2::new(10.0, 10.0), 1.0, 1.0 / 240.0);
+    water.parallel = parallel;
+
+    for i in 0..16 {
+        let i = i as isize;
+        water.disturb(size as isize * i / 16, size as isize * (15 - i) / 16, -1.0);
+    }
+
+    for _ in 0..WARMUP_STEPS {
+        water.update().unwrap();
+    }
+
+    water
+}
+
+fn bits(heights: &[f32]) -> Vec<u32> {
+    heights.iter().map(|h| h.to_bits()).collect()
+}
+
+fn assert_identical(size: usize) {
+    let serial = disturbed_water(size, false);
+    let parallel = disturbed_water(size, true);
+
+    assert_eq!(
+        bits(serial.heights()),
+        bits(parallel.heights()),
+        "serial and parallel heights differ on a {0}x{0} grid",
+        size
+    );
+
+    assert_eq!(
+        serial.normal_texture().image.as_bytes(),
+        parallel.normal_texture().image.as_bytes(),
+        "serial and parallel normals differ on a {0}x{0} grid",
+        size
+    );
+}
+
+fn water_update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("water_update");
+
+    for size in SIZES {
+        assert_identical(size);
+
+        for (name, parallel) in [("serial", false), ("parallel", true)] {
+            let mut water = disturbed_water(size, parallel);
+            group.bench_with_input(BenchmarkId::new(name, size), &size, |b, _| {
+                b.iter(|| water.update().unwrap())
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn water_kernel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("water_kernel");
+
+    for size in SIZES {
+        let mut reference = disturbed_water(size, false);
+        group.bench_with_input(BenchmarkId::new("reference", size), &size, |b, _| {
+            b.iter(|| reference.update_reference().unwrap())
+        });
+
+        let mut optimized = disturbed_water(size, false);
+        group.bench_with_input(BenchmarkId::new("optimized", size), &size, |b, _| {
+            b.iter(|| optimized.update().unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn normal_texture(c: &mut Criterion) {
+    let mut group = c.benchmark_group("normal_texture");
+
+    for size in SIZES {
+        for (name, parallel) in [("serial", false), ("parallel", true)] {
+            let water = disturbed_water(size, parallel);
+            group.bench_with_input(BenchmarkId::new(name, size), &size, |b, _| {
+                b.iter(|| water.normal_texture())
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, water_update, water_kernel, normal_texture);
+criterion_main!(benches);
@@ -10,6 +10,7 @@ in VS_OUT {
     vec3 normal;
     vec3 tangent;
     vec2 tex;
+    vec3 tint;
 } fs_in;
 
 out vec4 color;
@@ -30,5 +31,6 @@ void main() {
     float specular_cos = max(0.0, sqrt(1.0 - dot_h_t * dot_h_t));
     float specular = specular_coeff * pow(specular_cos, specular_exp);
 
-    color = texture(texture_sampler, fs_in.tex) * light_intensity * (ambient + diffuse + specular);
+    vec4 albedo = texture(texture_sampler, fs_in.tex) * vec4(fs_in.tint, 1.0);
+    color = albedo * light_intensity * (ambient + diffuse + specular);
 }
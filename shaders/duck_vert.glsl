@@ -1,6 +1,5 @@
 #version 430
 
-uniform mat4 model_transform;
 uniform mat4 view_transform;
 uniform mat4 projection_transform;
 
@@ -8,11 +7,16 @@ layout (location = 0) in vec3 position;
 layout (location = 1) in vec3 normal;
 layout (location = 2) in vec2 tex;
 
+// Per instance
+layout (location = 3) in mat4 model_transform;
+layout (location = 7) in vec3 tint;
+
 out VS_OUT {
     vec3 position;
     vec3 normal;
     vec3 tangent;
     vec2 tex;
+    vec3 tint;
 } vs_out;
 
 vec3 tangent1() {
@@ -36,6 +40,7 @@ void main() {
     vs_out.tangent = normalize((vec_transform * vec4(tangent, 0.0)).xyz);
 
     vs_out.tex = tex;
+    vs_out.tint = tint;
 
     gl_Position =
         projection_transform *
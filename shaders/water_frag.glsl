@@ -9,6 +9,7 @@ uniform samplerCube skybox_sampler;
 in VS_OUT {
     vec3 position;
     vec2 tex;
+    vec2 sample_tex;
 } fs_in;
 
 out vec4 color;
@@ -34,7 +35,7 @@ vec3 intersect_ray(vec3 p, vec3 dir) {
 }
 
 void main() {
-    vec3 normal = texture(texture_sampler, fs_in.tex).xyz * 2.0 - vec3(1.0, 1.0, 1.0);
+    vec3 normal = normalize(texture(texture_sampler, fs_in.sample_tex).xyz);
     vec3 to_light = normalize(light_position - fs_in.position);
     vec3 to_observer = normalize(camera_position - fs_in.position);
 
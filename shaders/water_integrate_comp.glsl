@@ -0,0 +1,114 @@
+#version 430
+
+// Same scheme as Water::integrate_sample, the next heights overwrite the previous ones
+
+layout (local_size_x = 16, local_size_y = 16) in;
+
+layout (std430, binding = 0) readonly buffer Heights { float heights[]; };
+layout (std430, binding = 1) buffer PreviousHeights { float previous_heights[]; };
+layout (std430, binding = 2) readonly buffer SpeedScales { float speed_scales[]; };
+layout (std430, binding = 3) readonly buffer Land { float land[]; };
+
+uniform int width;
+uniform int depth;
+// Boundary kinds and sponge widths of the min x, max x, min z and max z edges
+uniform ivec4 boundaries;
+uniform ivec4 sponge_widths;
+
+uniform vec2 a_coeff;
+uniform vec2 derivative_step;
+uniform float timestep;
+uniform float wave_speed;
+uniform float damping_rate;
+uniform float sponge_strength;
+
+const int FIXED = 1;
+const int PERIODIC = 2;
+const int ABSORBING = 3;
+
+// Sample index along an axis, -1 beyond a fixed edge
+int resolve(int coord, int len, int min_boundary, int max_boundary) {
+    int boundary;
+    if (coord < 0) {
+        boundary = min_boundary;
+    } else if (coord >= len) {
+        boundary = max_boundary;
+    } else {
+        return coord;
+    }
+
+    if (boundary == FIXED) {
+        return -1;
+    }
+
+    // Stencils reach at most one sample beyond an edge
+    if (boundary == PERIODIC) {
+        return coord < 0 ? coord + len : coord - len;
+    }
+
+    return clamp(coord, 0, len - 1);
+}
+
+int height_index(int x, int y) {
+    x = resolve(x, width, boundaries.x, boundaries.y);
+    y = resolve(y, depth, boundaries.z, boundaries.w);
+    return x < 0 || y < 0 ? -1 : width * y + x;
+}
+
+float neighbor_height(int idx, int nx, int ny) {
+    int neighbor = height_index(nx, ny);
+
+    if (neighbor < 0) {
+        return 0.0;
+    }
+
+    return land[neighbor] > 0.5 ? heights[idx] : heights[neighbor];
+}
+
+float layer(int boundary, int layer_width, int distance) {
+    if (boundary != ABSORBING || distance >= layer_width) {
+        return 0.0;
+    }
+
+    float t = float(layer_width - distance) / float(layer_width);
+    return t * t / float(layer_width);
+}
+
+float sponge(int coord, int len, int min_boundary, int max_boundary, int min_width, int max_width) {
+    return max(
+        layer(min_boundary, min_width, coord),
+        layer(max_boundary, max_width, len - 1 - coord)
+    );
+}
+
+void main() {
+    int x = int(gl_GlobalInvocationID.x);
+    int y = int(gl_GlobalInvocationID.y);
+
+    if (x >= width || y >= depth) {
+        return;
+    }
+
+    int idx = width * y + x;
+    if (land[idx] > 0.5) {
+        previous_heights[idx] = 0.0;
+        return;
+    }
+
+    float speed_scale = speed_scales[idx];
+    vec2 a = a_coeff * speed_scale * speed_scale;
+    float b = 2.0 - 2.0 * a.x - 2.0 * a.y;
+
+    float sponge_coeff = max(
+        sponge(x, width, boundaries.x, boundaries.y, sponge_widths.x, sponge_widths.y) / derivative_step.x,
+        sponge(y, depth, boundaries.z, boundaries.w, sponge_widths.z, sponge_widths.w) / derivative_step.y
+    ) * wave_speed * speed_scale * sponge_strength;
+    float d = exp(-(damping_rate + sponge_coeff) * timestep);
+
+    vec2 n = vec2(
+        neighbor_height(idx, x - 1, y) + neighbor_height(idx, x + 1, y),
+        neighbor_height(idx, x, y - 1) + neighbor_height(idx, x, y + 1)
+    );
+
+    previous_heights[idx] = d * (dot(a, n) + b * heights[idx] - previous_heights[idx]);
+}
@@ -0,0 +1,73 @@
+#version 430
+
+// Heights interpolated between the previous and current step and their normals, like
+// Water::interpolate_heights and Water::normal_texture_of
+
+layout (local_size_x = 16, local_size_y = 16) in;
+
+layout (std430, binding = 0) readonly buffer Heights { float heights[]; };
+layout (std430, binding = 1) readonly buffer PreviousHeights { float previous_heights[]; };
+layout (std430, binding = 4) writeonly buffer Normals { vec4 normals[]; };
+layout (std430, binding = 5) writeonly buffer DisplayedHeights { float displayed_heights[]; };
+
+uniform int width;
+uniform int depth;
+// Boundary kinds of the min x, max x, min z and max z edges
+uniform ivec4 boundaries;
+
+uniform vec2 derivative_step;
+uniform float alpha;
+
+const int FIXED = 1;
+const int PERIODIC = 2;
+
+// Sample index along an axis, -1 beyond a fixed edge
+int resolve(int coord, int len, int min_boundary, int max_boundary) {
+    int boundary;
+    if (coord < 0) {
+        boundary = min_boundary;
+    } else if (coord >= len) {
+        boundary = max_boundary;
+    } else {
+        return coord;
+    }
+
+    if (boundary == FIXED) {
+        return -1;
+    }
+
+    // Stencils reach at most one sample beyond an edge
+    if (boundary == PERIODIC) {
+        return coord < 0 ? coord + len : coord - len;
+    }
+
+    return clamp(coord, 0, len - 1);
+}
+
+float height(int x, int y) {
+    x = resolve(x, width, boundaries.x, boundaries.y);
+    y = resolve(y, depth, boundaries.z, boundaries.w);
+
+    if (x < 0 || y < 0) {
+        return 0.0;
+    }
+
+    int idx = width * y + x;
+    return mix(previous_heights[idx], heights[idx], alpha);
+}
+
+void main() {
+    int x = int(gl_GlobalInvocationID.x);
+    int y = int(gl_GlobalInvocationID.y);
+
+    if (x >= width || y >= depth) {
+        return;
+    }
+
+    vec3 x_tangent = vec3(derivative_step.x, 0.5 * (height(x + 1, y) - height(x - 1, y)), 0.0);
+    vec3 z_tangent = vec3(0.0, 0.5 * (height(x, y + 1) - height(x, y - 1)), derivative_step.y);
+    vec3 normal = normalize(cross(z_tangent, x_tangent));
+
+    normals[width * y + x] = vec4(normal, 0.0);
+    displayed_heights[width * y + x] = height(x, y);
+}
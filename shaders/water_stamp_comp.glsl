@@ -0,0 +1,23 @@
+#version 430
+
+// Adds the heights of a disturbance patch to the water, see Water::stamp
+
+layout (local_size_x = 16, local_size_y = 16) in;
+
+layout (std430, binding = 0) buffer Heights { float heights[]; };
+layout (std430, binding = 6) readonly buffer Patch { float patch_heights[]; };
+
+uniform int width;
+uniform ivec2 patch_origin;
+uniform ivec2 patch_size;
+
+void main() {
+    ivec2 coords = ivec2(gl_GlobalInvocationID.xy);
+
+    if (coords.x >= patch_size.x || coords.y >= patch_size.y) {
+        return;
+    }
+
+    ivec2 sample_coords = patch_origin + coords;
+    heights[sample_coords.y * width + sample_coords.x] += patch_heights[coords.y * patch_size.x + coords.x];
+}
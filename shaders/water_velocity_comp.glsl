@@ -0,0 +1,22 @@
+#version 430
+
+// Rescales the velocity encoded by the previous heights, see Water::scale_velocity
+
+layout (local_size_x = 256) in;
+
+layout (std430, binding = 0) readonly buffer Heights { float heights[]; };
+layout (std430, binding = 1) buffer PreviousHeights { float previous_heights[]; };
+
+uniform int sample_count;
+uniform float scale;
+
+void main() {
+    int idx = int(gl_GlobalInvocationID.x);
+
+    if (idx >= sample_count) {
+        return;
+    }
+
+    float height = heights[idx];
+    previous_heights[idx] = height - scale * (height - previous_heights[idx]);
+}
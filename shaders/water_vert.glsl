@@ -3,16 +3,23 @@
 uniform mat4 model_transform;
 uniform mat4 view_transform;
 uniform mat4 projection_transform;
+uniform sampler2D height_sampler;
 
 layout (location = 0) in vec3 position;
 
 out VS_OUT {
     vec3 position;
     vec2 tex;
+    vec2 sample_tex;
 } vs_out;
 
 void main() {
-    vs_out.position = (model_transform * vec4(position, 1.0)).xyz;
+    // Grid vertices lie on the water samples, which are at the texel centers
+    vec2 size = vec2(textureSize(height_sampler, 0));
+    vs_out.sample_tex = (position.xz * (size - 1.0) + 0.5) / size;
+
+    float height = textureLod(height_sampler, vs_out.sample_tex, 0.0).r;
+    vs_out.position = (model_transform * vec4(position.x, height, position.z, 1.0)).xyz;
 
     gl_Position =
         projection_transform *
@@ -0,0 +1,88 @@
+use crate::render::texture::Texture;
+use image::imageops::FilterType;
+
+/// Depth of the pool at every sample of a water grid, expressed as a multiplier of the wave
+/// speed
+#[derive(Clone, Debug)]
+pub struct Bathymetry {
+    width: usize,
+    depth: usize,
+    speed_scales: Vec<f32>,
+}
+
+impl Bathymetry {
+    /// Smallest speed multiplier produced from depths, so that dry samples still carry waves
+    const MIN_SPEED_SCALE: f32 = 0.05;
+
+    /// Constant depth everywhere, waves travel with the base wave speed of the water
+    pub fn uniform(width: usize, depth: usize) -> Self {
+        Self::from_speed_scales(width, depth, vec![1.0; width * depth])
+    }
+
+    /// `speed_scales` are stored row by row, `width` samples per row, and have to be positive
+    pub fn from_speed_scales(width: usize, depth: usize, speed_scales: Vec<f32>) -> Self {
+        assert_eq!(speed_scales.len(), width * depth);
+        assert!(speed_scales.iter().all(|scale| *scale > 0.0));
+
+        Self {
+            width,
+            depth,
+            speed_scales,
+        }
+    }
+
+    /// Uses the shallow water approximation, in which the wave speed is proportional to the
+    /// square root of the depth. Samples at `reference_depth` get the base wave speed.
+    pub fn from_depths(width: usize, depth: usize, depths: &[f32], reference_depth: f32) -> Self {
+        let speed_scales = depths
+            .iter()
+            .map(|d| (d / reference_depth).sqrt().max(Self::MIN_SPEED_SCALE))
+            .collect();
+
+        Self::from_speed_scales(width, depth, speed_scales)
+    }
+
+    /// Reads a grayscale heightmap of the bottom of the pool. Black is the deepest point,
+    /// which gets the base wave speed, and white is the water surface.
+    pub fn from_heightmap(texture: &Texture, width: usize, depth: usize) -> Self {
+        let image = texture
+            .image
+            .resize_exact(width as u32, depth as u32, FilterType::Triangle)
+            .into_luma8();
+
+        let depths: Vec<_> = image
+            .pixels()
+            .map(|p| 1.0 - p.0[0] as f32 / u8::MAX as f32)
+            .collect();
+
+        Self::from_depths(width, depth, &depths, 1.0)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn speed_scale(&self, x: usize, y: usize) -> f32 {
+        self.speed_scales[self.width * y + x]
+    }
+
+    pub fn speed_scales(&self) -> &[f32] {
+        &self.speed_scales
+    }
+
+    pub(crate) fn speed_scale_index(&self, idx: usize) -> f32 {
+        self.speed_scales[idx]
+    }
+
+    pub(crate) fn row(&self, y: usize) -> &[f32] {
+        &self.speed_scales[self.width * y..self.width * (y + 1)]
+    }
+
+    pub fn max_speed_scale(&self) -> f32 {
+        self.speed_scales.iter().copied().fold(0.0, f32::max)
+    }
+}
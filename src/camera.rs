@@ -1,6 +1,6 @@
-use crate::math::affine::transforms;
+use crate::math::{affine::transforms, geometry::ray::Ray};
 use glutin::dpi::PhysicalSize;
-use nalgebra::{Matrix4, Point3, Vector3, Vector4};
+use nalgebra::{Matrix4, Point2, Point3, Vector3, Vector4};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Camera {
@@ -94,6 +94,14 @@ impl Camera {
             self.far_plane,
         )
     }
+
+    /// Ray from the camera through a point on the screen in normalized device coordinates
+    pub fn ray(&self, ndc: Point2<f32>) -> Ray {
+        let view = self.inverse_projection_transform() * Vector4::new(ndc.x, ndc.y, -1.0, 1.0);
+        let world = self.inverse_view_transform() * (view / view.w);
+
+        Ray::new(self.position, world.xyz() - self.position.coords)
+    }
 }
 
 impl Default for Camera {
@@ -0,0 +1,390 @@
+use crate::{
+    floater::Floater,
+    math::{
+        affine::transforms,
+        geometry::{bezier::BezierBSpline, curve::Curve3},
+    },
+    path_planner::{PathCurve, PathPlanner, RandomWander},
+    primitives::color::Color,
+    wake::WakeEmitter,
+    water::Water,
+    water_disturbance::{Disturbance, DisturbanceKernel},
+};
+use nalgebra::{Matrix4, Point2, Point3, Vector2, Vector3};
+use std::ops::{Deref, Range};
+
+/// Duck swimming along its own path. The path is extended by the duck's `planner`, the duck
+/// follows it, floats on the water and steers clear of other ducks.
+#[derive(Clone, Debug)]
+pub struct Duck {
+    /// Points the path is built from
+    points: Vec<Point3<f32>>,
+    curve: PathCurve,
+    /// Largest distance of the path from the center of the pool along either axis, see
+    /// `PlannerContext::bounds`. Unbounded until the duck is added to a `Simulation`.
+    bounds: Vector2<f32>,
+    path: Box<dyn Curve3>,
+    /// Parameter of the position on the path
+    progress: f32,
+    /// Distance the duck moved past the end of its path, carried over to the next one
+    overshoot: f32,
+    /// Speed along the path in meters per second
+    pub speed: f32,
+    pub drift: bool,
+    /// Color multiplied with the texture of the duck
+    pub tint: Color,
+    /// Chooses the points the path is extended with, `RandomWander` by default
+    pub planner: Box<dyn PathPlanner>,
+
+    /// Offset from the path that keeps the duck away from other ducks
+    avoidance: Vector2<f32>,
+    floater: Floater,
+    wake: WakeEmitter,
+    mtx: Matrix4<f32>,
+}
+
+impl Duck {
+    pub const DEFAULT_SPEED: f32 = 3.0;
+    /// Scale of the duck model
+    pub const SCALE: f32 = 0.01;
+    /// Bounding radius of the duck model times `SCALE`, rounded up
+    pub const MODEL_RADIUS: f32 = 1.3;
+
+    const HALF_LENGTH: f32 = 0.9;
+    const HALF_WIDTH: f32 = 0.35;
+    const HULL_SAMPLES: usize = 3;
+    const DRAFT: f32 = 0.15;
+    const DAMPING_RATIO: f32 = 0.3;
+    const RIM_SAMPLES: usize = 16;
+    /// Size of the ring the hull pushes water to, relative to the hull
+    const RIM_SCALE: f32 = 1.25;
+    const RIM_RADIUS: f32 = 0.2;
+
+    const WAKE_SPACING: f32 = 0.1;
+    const WAKE_RADIUS: f32 = 0.2;
+    const WAKE_VOLUME: f32 = 0.0075;
+
+    /// Distance between the centers of two ducks below which they push each other away
+    pub const SEPARATION_RADIUS: f32 = 2.5 * Self::HALF_LENGTH;
+    /// Speed at which two touching ducks move apart
+    const SEPARATION_SPEED: f32 = 2.0;
+    /// Rate at which a duck returns to its path once nothing is in its way
+    const AVOIDANCE_RELAXATION: f32 = 0.5;
+    const MAX_AVOIDANCE: f32 = 1.5;
+    const UNBOUNDED: Vector2<f32> = Vector2::new(f32::INFINITY, f32::INFINITY);
+
+    pub fn new(path: BezierBSpline, tint: Color) -> Self {
+        Self::with_path(
+            path.deboor_points(),
+            PathCurve::BSpline,
+            Box::new(path),
+            tint,
+        )
+    }
+
+    /// Duck on the `curve` built from `points`
+    pub fn with_curve(points: Vec<Point3<f32>>, curve: PathCurve, tint: Color) -> Self {
+        let path = curve.build(points.clone(), Self::UNBOUNDED);
+        Self::with_path(points, curve, path, tint)
+    }
+
+    fn with_path(
+        points: Vec<Point3<f32>>,
+        curve: PathCurve,
+        path: Box<dyn Curve3>,
+        tint: Color,
+    ) -> Self {
+        let progress = path.domain().start;
+        let position = path.value(progress);
+
+        Self {
+            points,
+            curve,
+            bounds: Self::UNBOUNDED,
+            path,
+            progress,
+            overshoot: 0.0,
+            speed: Self::DEFAULT_SPEED,
+            drift: false,
+            tint,
+            planner: Box::new(RandomWander),
+
+            avoidance: Vector2::zeros(),
+            floater: Floater::new(
+                Floater::elliptic_hull(Self::HALF_LENGTH, Self::HALF_WIDTH, Self::HULL_SAMPLES),
+                Self::DRAFT,
+                Self::DAMPING_RATIO,
+            ),
+            wake: WakeEmitter::new(
+                Self::WAKE_SPACING,
+                Self::WAKE_RADIUS,
+                Self::WAKE_VOLUME,
+                Self::HALF_LENGTH,
+            ),
+            mtx: transforms::translate(position.coords) * transforms::uniform_scale(Self::SCALE),
+        }
+    }
+
+    pub fn mtx(&self) -> &Matrix4<f32> {
+        &self.mtx
+    }
+
+    pub fn points(&self) -> &[Point3<f32>] {
+        &self.points
+    }
+
+    pub fn curve(&self) -> PathCurve {
+        self.curve
+    }
+
+    /// Rebuilds the path from the same points as `curve`, at the same fraction of its domain
+    pub fn set_curve(&mut self, curve: PathCurve) {
+        self.curve = curve;
+        self.rebuild_path();
+    }
+
+    /// Rebuilds the path within `bounds`, at the same fraction of its domain
+    pub(crate) fn set_bounds(&mut self, bounds: Vector2<f32>) {
+        self.bounds = bounds;
+        self.rebuild_path();
+    }
+
+    fn rebuild_path(&mut self) {
+        let domain = self.path.domain();
+        let fraction = (self.progress - domain.start) / (domain.end - domain.start);
+
+        self.path = self.curve.build(self.points.clone(), self.bounds);
+
+        let domain = self.path.domain();
+        self.progress = domain.start + fraction * (domain.end - domain.start);
+    }
+
+    pub fn path(&self) -> DuckPath<'_> {
+        DuckPath {
+            points: &self.points,
+            curve: self.path.as_ref(),
+        }
+    }
+
+    /// Parameter of the position on the path, within its domain
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    pub fn floater(&self) -> &Floater {
+        &self.floater
+    }
+
+    /// Position on the water plane, including the offset from the path
+    pub fn position(&self) -> Point2<f32> {
+        let position = self.path.value(self.progress);
+        Point2::new(position.x, position.z) + self.avoidance
+    }
+
+    /// Advances the duck along its path and returns the range of the path it covered, which
+    /// ends at the end of the domain when the path has to be extended
+    pub(crate) fn advance(&mut self, delta: f32) -> Range<f32> {
+        let speed_multiplier = if self.drift { 2.0 } else { 1.0 };
+        let distance = speed_multiplier * delta * self.speed;
+
+        let previous_progress = self.progress;
+        let length = self.path.arc_length(self.progress) + distance;
+        let path_length = self.path.length();
+
+        if length >= path_length {
+            self.overshoot = length - path_length;
+            self.progress = self.path.domain().end;
+        } else {
+            self.progress = self.path.parameter_at_length(length);
+        }
+
+        previous_progress..self.progress
+    }
+
+    /// Replaces the path with its continuation through `points`, the distance moved past the
+    /// end of the old path carries over to the new one
+    pub(crate) fn extend_path(&mut self, points: Vec<Point3<f32>>) {
+        let path = self.curve.build(points.clone(), self.bounds);
+        let end = path.domain().end;
+        self.progress = path
+            .parameter_at_length(self.overshoot)
+            .min(end - end.abs().max(1.0) * f32::EPSILON);
+        self.overshoot = 0.0;
+        self.points = points;
+        self.path = path;
+    }
+
+    /// Puts the duck at the start of a new path through `points`
+    pub(crate) fn reset_path(&mut self, points: Vec<Point3<f32>>) {
+        self.path = self.curve.build(points.clone(), self.bounds);
+        self.progress = self.path.domain().start;
+        self.overshoot = 0.0;
+        self.points = points;
+    }
+
+    /// Moves the duck away from the ducks at `others` that come too close, and back towards
+    /// its path when none do. The duck is not pushed further than `bounds` from the origin
+    /// along either axis.
+    pub(crate) fn steer_apart(
+        &mut self,
+        delta: f32,
+        others: impl Iterator<Item = Point2<f32>>,
+        bounds: Vector2<f32>,
+    ) {
+        let position = self.position();
+        let push: Vector2<f32> = others
+            .map(|other| position - other)
+            .filter(|offset| offset.norm() < Self::SEPARATION_RADIUS)
+            .map(|offset| {
+                let distance = offset.norm();
+                // Ducks on top of each other split along an arbitrary direction
+                let direction = offset.try_normalize(f32::EPSILON).unwrap_or(Vector2::x());
+                (1.0 - distance / Self::SEPARATION_RADIUS) * direction
+            })
+            .sum();
+
+        let velocity = if push == Vector2::zeros() {
+            -Self::AVOIDANCE_RELAXATION * self.avoidance
+        } else {
+            Self::SEPARATION_SPEED * push
+        };
+
+        self.avoidance += velocity * delta;
+        self.avoidance = self.avoidance.cap_magnitude(Self::MAX_AVOIDANCE);
+
+        let on_path = self.position() - self.avoidance;
+        let position = self
+            .position()
+            .coords
+            .zip_map(&bounds, |x, bound| x.clamp(-bound, bound));
+        self.avoidance = position - on_path.coords;
+    }
+
+    /// Disturbances of the wake left along `progress` on the path
+    pub(crate) fn wake(&mut self, progress: Range<f32>) -> Vec<Disturbance> {
+        let disturbance_multiplier = if self.drift { 50.0 } else { 1.0 };
+        let mut disturbances = self
+            .wake
+            .emit(self.path.as_ref(), progress, disturbance_multiplier);
+
+        for disturbance in &mut disturbances {
+            disturbance.x += self.avoidance.x;
+            disturbance.z += self.avoidance.y;
+        }
+
+        disturbances
+    }
+
+    /// Places the duck on the water at its current progress and returns the disturbances its
+    /// hull makes. With `buoyancy` the duck floats as a rigid body, otherwise it sticks to the
+    /// surface and leans with its slope.
+    pub(crate) fn place(&mut self, delta: f32, water: &Water, buoyancy: bool) -> Vec<Disturbance> {
+        let center = self.position();
+        let mut position = self.path.value(self.progress);
+        position.x = center.x;
+        position.z = center.y;
+
+        let tangent = self.path.tangent(self.progress);
+        let angle = if self.drift {
+            f32::atan2(tangent.z, tangent.x)
+        } else {
+            f32::atan2(tangent.x, tangent.z)
+        } + std::f32::consts::FRAC_PI_2;
+
+        let mut disturbances = Vec::new();
+        let tilt = if buoyancy {
+            let forward = Vector2::new(tangent.x, tangent.z);
+            disturbances = self.float(delta, water, center, forward);
+
+            position.y += self.floater.heave;
+            self.floater.orientation(forward)
+        } else {
+            position.y += water.surface_height(center.x, center.y);
+            let normal = water.surface_normal(center.x, center.y);
+            transforms::rotate_axis(
+                Vector3::y().cross(&normal),
+                normal.y.clamp(-1.0, 1.0).acos(),
+            )
+        };
+
+        self.mtx = if self.drift {
+            transforms::translate(position.coords)
+                * tilt
+                * transforms::rotate_y(angle)
+                * transforms::rotate_x(angle / 4.0)
+                * transforms::rotate_z(angle / 4.0)
+                * transforms::uniform_scale(Self::SCALE)
+        } else {
+            transforms::translate(position.coords)
+                * tilt
+                * transforms::rotate_y(angle)
+                * transforms::uniform_scale(Self::SCALE)
+        };
+
+        disturbances
+    }
+
+    /// Steps the buoyancy of the duck. The water its hull pushes away while sinking rises on a
+    /// ring around it, and sinks there while the hull rises, which radiates waves. Raising the
+    /// water under the hull itself would feed back into the buoyancy.
+    fn float(
+        &mut self,
+        delta: f32,
+        water: &Water,
+        center: Point2<f32>,
+        forward: Vector2<f32>,
+    ) -> Vec<Disturbance> {
+        let surface = |point: Point2<f32>| water.surface_height(point.x, point.y);
+        self.floater.step(delta, center, forward, surface);
+
+        let volume = self.floater.displacement_rate(center, forward, surface) * delta;
+
+        let forward = forward.normalize();
+        let side = Vector2::new(-forward.y, forward.x);
+
+        (0..Self::RIM_SAMPLES)
+            .map(|i| {
+                let angle = std::f32::consts::TAU * i as f32 / Self::RIM_SAMPLES as f32;
+                let point = center
+                    + Self::RIM_SCALE
+                        * (forward * Self::HALF_LENGTH * angle.cos()
+                            + side * Self::HALF_WIDTH * angle.sin());
+
+                Disturbance::with_volume(
+                    point.x,
+                    point.y,
+                    Self::RIM_RADIUS,
+                    volume / Self::RIM_SAMPLES as f32,
+                    DisturbanceKernel::RaisedCosine,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Path of a duck, usable as the curve itself
+#[derive(Clone, Copy)]
+pub struct DuckPath<'a> {
+    points: &'a [Point3<f32>],
+    curve: &'a dyn Curve3,
+}
+
+impl<'a> DuckPath<'a> {
+    /// Points the path is built from
+    pub fn points(&self) -> &'a [Point3<f32>] {
+        self.points
+    }
+
+    /// Points the path is built from, which are its de Boor points for a B-spline path
+    pub fn deboor_points(&self) -> Vec<Point3<f32>> {
+        self.points.to_vec()
+    }
+}
+
+impl<'a> Deref for DuckPath<'a> {
+    type Target = dyn Curve3 + 'a;
+
+    fn deref(&self) -> &Self::Target {
+        self.curve
+    }
+}
@@ -1,10 +1,15 @@
 use crate::{
     camera::Camera,
     constants::*,
+    duck::Duck,
     keyboard::KeyboardState,
-    math::{affine::transforms, geometry::bezier::BezierBSpline},
+    math::affine::{screen, transforms},
     mouse::MouseState,
-    primitives::vertex::SimpleVertex,
+    path_planner::{FigureEight, FollowCursor, PathCurve, RandomWander, WaypointLoop},
+    primitives::{
+        color::Color,
+        vertex::{DuckInstance, DuckVertex, SimpleVertex},
+    },
     render::{
         gl_drawable::GlDrawable,
         gl_mesh::GlMesh,
@@ -15,15 +20,17 @@ use crate::{
         texture::Texture,
     },
     shaders,
-    water_texture::WaterTexture,
+    simulation::Simulation,
+    water::Water,
+    water_backend::{WaterBackend, WaterBackendKind},
+    water_disturbance::{Disturbance, DisturbanceKernel},
 };
 use glow::HasContext;
 use glutin::{
     dpi::PhysicalPosition,
     event::{Event, VirtualKeyCode, WindowEvent},
 };
-use nalgebra::{Matrix4, Point3, Vector3};
-use rand::{distributions, rngs::ThreadRng, thread_rng, Rng};
+use nalgebra::{Matrix4, Point2, Point3, Vector2, Vector3};
 use std::{path::Path, time::Duration};
 
 pub struct DuckApp<'gl> {
@@ -36,18 +43,24 @@ pub struct DuckApp<'gl> {
     mouse: MouseState,
     keyboard: KeyboardState,
 
+    simulation: Simulation,
+
     duck_mesh: GlMesh<'gl>,
     duck_texture: GlTexture<'gl>,
-    duck_mtx: Matrix4<f32>,
-
-    duck_path: BezierBSpline,
-    duck_progress: f32,
-    duck_speed: f32,
-    duck_drift: bool,
 
     water_mesh: GlMesh<'gl>,
-    water_texture: WaterTexture<'gl>,
+    water_backend: Box<dyn WaterBackend + 'gl>,
     water_mtx: Matrix4<f32>,
+    /// Radius of the splash made by the right mouse button
+    splash_radius: f32,
+    /// Depth per second of the splash made by the right mouse button
+    splash_strength: f32,
+
+    /// Waypoints added with the middle mouse button
+    route: Vec<Point2<f32>>,
+    route_path: String,
+    /// Outcome of the last route load or save
+    route_status: String,
 
     skybox_mesh: GlMesh<'gl>,
     skybox_texture: GlCubeTexture<'gl>,
@@ -55,38 +68,39 @@ pub struct DuckApp<'gl> {
 
     light_position: Vector3<f32>,
     light_intensity: f32,
-
-    rng: ThreadRng,
-    uniform_dist: distributions::Uniform<f32>,
 }
 
 impl<'gl> DuckApp<'gl> {
     const CAMERA_ROTATION_SPEED: f32 = 0.5;
     const CAMERA_MOVEMENT_SPEED: f32 = 5.0;
 
-    const DEFAULT_DUCK_SPEED: f32 = 1.0;
-    const DUCK_SCALE: f32 = 0.01;
-    const DUCK_Y: f32 = -2.7;
-    const DUCK_DISTURBANCE: f32 = -0.3;
-
-    const WATER_SAMPLES: usize = 256;
-    const DEFAULT_WAVE_SPEED: f32 = 0.75;
-    const RAIN_CHANCE: f32 = 1.3e-6;
-    const RAIN_DISTURBANCE: f32 = -0.1;
-
     const DEFAULT_LIGHT_POSITION: Vector3<f32> = Vector3::new(0.0, 1.0, 0.0);
     const DEFAULT_LIGHT_INTENSITY: f32 = 1.0;
 
-    const ENVIRONMENT_SCALE: f32 = 10.0;
+    const WATER_LEVEL: f32 = -2.5;
+    const DEFAULT_SPLASH_RADIUS: f32 = 0.3;
+    const DEFAULT_SPLASH_STRENGTH: f32 = 5.0;
 
-    pub fn init(gl: &'gl glow::Context) -> Self {
+    const DEFAULT_ROUTE_PATH: &'static str = "routes/pond.route";
+    const FIGURE_EIGHT_HALF_EXTENT: Vector2<f32> = Vector2::new(3.5, 2.5);
+    const FOLLOW_STEP: f32 = 1.5;
+
+    pub fn init(
+        gl: &'gl glow::Context,
+        mut simulation: Simulation,
+        water_backend: WaterBackendKind,
+    ) -> Self {
         let duck = Mesh::from_file(Path::new(DUCK_MODEL_PATH));
-        let duck_mesh = GlMesh::new(gl, &duck);
+        simulation.set_duck_radius(duck.bounding_radius() * Duck::SCALE);
+        let duck_mesh = GlMesh::instanced::<DuckVertex, DuckInstance>(gl, &duck);
 
         let duck_texture = Texture::from_file(Path::new(&DUCK_TEXTURE_PATH));
         let duck_texture = GlTexture::new(gl, &duck_texture);
 
-        let water_mesh = Mesh::<SimpleVertex>::rect();
+        let water_mesh = Mesh::<SimpleVertex>::grid(
+            simulation.water().width() as u32,
+            simulation.water().depth() as u32,
+        );
 
         let skybox_mesh = Mesh::<SimpleVertex>::inner_cube();
         let skybox_textures: [Texture; 6] = SKYBOX_TEXTURE_PATHS
@@ -96,10 +110,10 @@ impl<'gl> DuckApp<'gl> {
             .try_into()
             .unwrap();
 
-        let environment_transform = transforms::uniform_scale(Self::ENVIRONMENT_SCALE)
+        let environment_transform = transforms::uniform_scale(Simulation::ENVIRONMENT_SCALE)
             * transforms::translate(Vector3::new(-0.5, 0.0, -0.5));
 
-        let mut rng = thread_rng();
+        let water_extent = simulation.water().extent();
 
         Self::init_gl(gl);
 
@@ -115,19 +129,20 @@ impl<'gl> DuckApp<'gl> {
 
             duck_mesh,
             duck_texture,
-            duck_mtx: transforms::translate(Vector3::new(0.0, Self::DUCK_Y, 0.0))
-                * transforms::uniform_scale(Self::DUCK_SCALE),
 
             water_mesh: GlMesh::new(gl, &water_mesh),
-            water_texture: WaterTexture::new(gl, Self::WATER_SAMPLES, Self::DEFAULT_WAVE_SPEED),
-            water_mtx: transforms::translate(Vector3::new(0.0, -2.5, 0.0)) * environment_transform,
+            water_backend: water_backend.create(gl, &mut simulation),
+            water_mtx: transforms::translate(Vector3::new(0.0, Self::WATER_LEVEL, 0.0))
+                * transforms::scale(water_extent.x, 1.0, water_extent.y)
+                * transforms::translate(Vector3::new(-0.5, 0.0, -0.5)),
+            splash_radius: Self::DEFAULT_SPLASH_RADIUS,
+            splash_strength: Self::DEFAULT_SPLASH_STRENGTH,
+
+            route: Vec::new(),
+            route_path: Self::DEFAULT_ROUTE_PATH.to_owned(),
+            route_status: String::new(),
 
-            duck_path: BezierBSpline::through_points(
-                (0..4).map(|_| Self::random_path_point(&mut rng)).collect(),
-            ),
-            duck_progress: 0.0,
-            duck_speed: Self::DEFAULT_DUCK_SPEED,
-            duck_drift: false,
+            simulation,
 
             skybox_mesh: GlMesh::new(gl, &skybox_mesh),
             skybox_texture: GlCubeTexture::new(gl, &skybox_textures),
@@ -135,9 +150,6 @@ impl<'gl> DuckApp<'gl> {
 
             light_position: Self::DEFAULT_LIGHT_POSITION,
             light_intensity: Self::DEFAULT_LIGHT_INTENSITY,
-
-            rng: thread_rng(),
-            uniform_dist: distributions::Uniform::new(0.0, 1.0),
         }
     }
 
@@ -151,26 +163,73 @@ impl<'gl> DuckApp<'gl> {
     }
 
     pub fn update(&mut self, delta: Duration, mouse_captured: bool) {
-        self.update_water();
+        self.simulation.step(delta);
+        self.water_backend.update(&mut self.simulation);
+        self.update_duck_instances();
+
         self.update_position(delta);
 
         if !mouse_captured {
             self.update_view(delta);
         }
 
-        self.update_duck(delta);
+        if !mouse_captured {
+            let hit = self.cursor_hit();
+            self.simulation.cursor = hit.map(|hit| Point2::new(hit.x, hit.z));
+            self.update_splash(delta);
+            self.update_route();
+        }
     }
 
-    fn update_water(&mut self) {
-        for x in 0..(Self::WATER_SAMPLES as isize) {
-            for y in 0..(Self::WATER_SAMPLES as isize) {
-                if self.rng.sample(self.uniform_dist) < Self::RAIN_CHANCE {
-                    self.water_texture.disturb(x, y, Self::RAIN_DISTURBANCE);
-                }
-            }
+    /// Point on the water plane under the cursor
+    fn cursor_hit(&self) -> Option<Point3<f32>> {
+        let cursor = self.mouse.integer_position()?;
+        let ndc = screen::screen_to_ndc(&self.camera.resolution, &cursor);
+
+        self.camera
+            .ray(ndc)
+            .intersect_horizontal_plane(Self::WATER_LEVEL)
+    }
+
+    /// Pushes the water down under the cursor while the right mouse button is held
+    fn update_splash(&mut self, delta: Duration) {
+        if !self.mouse.is_right_button_down() {
+            return;
         }
 
-        self.water_texture.update();
+        let Some(hit) = self.cursor_hit() else {
+            return;
+        };
+
+        self.simulation.stamp_water(Disturbance::new(
+            hit.x,
+            hit.z,
+            self.splash_radius,
+            -self.splash_strength * delta.as_secs_f32(),
+            DisturbanceKernel::RaisedCosine,
+        ));
+    }
+
+    /// Appends the point under the cursor to the route when the middle mouse button is clicked
+    fn update_route(&mut self) {
+        if !self.mouse.has_middle_button_been_pressed() {
+            return;
+        }
+
+        if let Some(cursor) = self.simulation.cursor {
+            self.route.push(cursor);
+        }
+    }
+
+    fn update_duck_instances(&mut self) {
+        let instances: Vec<_> = self
+            .simulation
+            .ducks()
+            .iter()
+            .map(|duck| DuckInstance::new(*duck.mtx(), duck.tint))
+            .collect();
+
+        self.duck_mesh.set_instances(&instances);
     }
 
     fn update_position(&mut self, delta: Duration) {
@@ -227,66 +286,6 @@ impl<'gl> DuckApp<'gl> {
         self.last_mouse_position = self.mouse.position();
     }
 
-    fn update_duck(&mut self, delta: Duration) {
-        let speed_multiplier = if self.duck_drift { 2.0 } else { 1.0 };
-
-        self.duck_progress += speed_multiplier * delta.as_secs_f32() * self.duck_speed;
-
-        if self.duck_progress >= 1.0 {
-            self.add_new_path_point();
-        }
-
-        let position = self.duck_path.value(self.duck_progress);
-        let tangent = self.duck_path.tangent(self.duck_progress);
-        let angle = if self.duck_drift {
-            f32::atan2(tangent.z, tangent.x)
-        } else {
-            f32::atan2(tangent.x, tangent.z)
-        } + std::f32::consts::FRAC_PI_2;
-
-        self.duck_mtx = if self.duck_drift {
-            transforms::translate(position.coords)
-                * transforms::rotate_y(angle)
-                * transforms::rotate_x(angle / 4.0)
-                * transforms::rotate_z(angle / 4.0)
-                * transforms::uniform_scale(Self::DUCK_SCALE)
-        } else {
-            transforms::translate(position.coords)
-                * transforms::rotate_y(angle)
-                * transforms::uniform_scale(Self::DUCK_SCALE)
-        };
-
-        let water_pos = (position / Self::ENVIRONMENT_SCALE + Vector3::new(0.5, 0.5, 0.5))
-            * Self::WATER_SAMPLES as f32;
-
-        let disturbance_multiplier = if self.duck_drift { 50.0 } else { 1.0 };
-
-        self.water_texture.disturb(
-            water_pos.x.round() as isize,
-            water_pos.z.round() as isize,
-            disturbance_multiplier * Self::DUCK_DISTURBANCE,
-        );
-    }
-
-    fn add_new_path_point(&mut self) {
-        self.duck_progress = self.duck_progress.fract();
-        let mut coeffs = self.duck_path.deboor_points();
-        coeffs.remove(0);
-        coeffs.push(Self::random_path_point(&mut self.rng));
-        self.duck_path = BezierBSpline::through_points(coeffs);
-    }
-
-    fn random_path_point(rng: &mut impl Rng) -> Point3<f32> {
-        let dist = distributions::Uniform::new(
-            -0.5 * Self::ENVIRONMENT_SCALE,
-            0.5 * Self::ENVIRONMENT_SCALE,
-        );
-
-        let x = rng.sample(dist);
-        let z = rng.sample(dist);
-        Point3::new(x, Self::DUCK_Y, z)
-    }
-
     fn clear(&self) {
         unsafe {
             self.gl
@@ -304,7 +303,6 @@ impl<'gl> DuckApp<'gl> {
     fn render_duck(&self) {
         let program = self.shader_manager.program("duck");
         program.enable();
-        program.uniform_matrix_4_f32("model_transform", &self.duck_mtx);
         self.basic_camera_uniforms(program);
         program.uniform_3_f32(
             "camera_position",
@@ -335,14 +333,28 @@ impl<'gl> DuckApp<'gl> {
         unsafe {
             self.gl.active_texture(glow::TEXTURE0);
         }
-        self.water_texture.normal_texture().bind();
+        self.water_backend.bind_normal_texture();
+
+        program.uniform_i32("height_sampler", 2);
+        unsafe {
+            self.gl.active_texture(glow::TEXTURE2);
+        }
+        self.water_backend.bind_height_texture();
 
         program.uniform_i32("skybox_sampler", 1);
         unsafe {
             self.gl.active_texture(glow::TEXTURE1);
         }
         self.skybox_texture.bind();
+
+        // The grid only faces up, but the surface is also visible from below
+        unsafe {
+            self.gl.disable(glow::CULL_FACE);
+        }
         self.water_mesh.draw();
+        unsafe {
+            self.gl.enable(glow::CULL_FACE);
+        }
     }
 
     fn render_skybox(&self) {
@@ -381,6 +393,7 @@ impl<'gl> DuckApp<'gl> {
                 self.camera_control(ui);
                 self.light_control(ui);
                 self.duck_control(ui);
+                self.water_control(ui);
             });
     }
 
@@ -404,8 +417,179 @@ impl<'gl> DuckApp<'gl> {
     }
 
     fn duck_control(&mut self, ui: &imgui::Ui) {
-        ui.slider("Duck speed", 0.0, 5.0, &mut self.duck_speed);
-        ui.checkbox("Duck drift", &mut self.duck_drift);
+        ui.checkbox("Duck buoyancy", &mut self.simulation.duck_buoyancy);
+
+        if ui.button("Add duck") {
+            self.simulation.add_duck();
+        }
+        ui.same_line();
+        if ui.button("Remove duck") && !self.simulation.ducks().is_empty() {
+            self.simulation
+                .remove_duck(self.simulation.ducks().len() - 1);
+        }
+
+        self.route_control(ui);
+        let route = (self.route.len() >= WaypointLoop::MIN_WAYPOINTS)
+            .then(|| WaypointLoop::new(self.route.clone()));
+        let bounds = self.simulation.duck_bounds();
+
+        let duck_count = self.simulation.ducks().len();
+        for (idx, duck) in self.simulation.ducks_iter_mut().enumerate() {
+            let _id = ui.push_id_usize(idx);
+            ui.text(format!("Duck {}", idx + 1));
+            ui.slider("Speed [m/s]", 0.0, 10.0, &mut duck.speed);
+            ui.checkbox("Drift", &mut duck.drift);
+
+            if ui.button("Wander") {
+                duck.planner = Box::new(RandomWander);
+            }
+            ui.same_line();
+            if ui.button("Route") {
+                match route
+                    .as_ref()
+                    .map(|route| (route, route.check_bounds(bounds)))
+                {
+                    Some((route, Ok(()))) => {
+                        let start = idx * route.waypoints().len() / duck_count;
+                        duck.planner = Box::new(route.clone().starting_at(start));
+                    }
+                    Some((_, Err(msg))) => {
+                        self.route_status = format!("Cannot follow route: {}", msg)
+                    }
+                    None => {
+                        self.route_status = format!(
+                            "Cannot follow route: it needs at least {} waypoints",
+                            WaypointLoop::MIN_WAYPOINTS
+                        )
+                    }
+                }
+            }
+            ui.same_line();
+            if ui.button("Figure eight") {
+                duck.planner = Box::new(FigureEight::new(
+                    Point2::origin(),
+                    Self::FIGURE_EIGHT_HALF_EXTENT,
+                ));
+            }
+            ui.same_line();
+            if ui.button("Follow cursor") {
+                duck.planner = Box::new(FollowCursor::new(Self::FOLLOW_STEP));
+            }
+
+            let mut curve = duck.curve();
+            if ui.radio_button_bool("B-spline", curve == PathCurve::BSpline) {
+                curve = PathCurve::BSpline;
+            }
+            ui.same_line();
+            if ui.radio_button_bool("Catmull-Rom", curve == PathCurve::CatmullRom) {
+                curve = PathCurve::CatmullRom;
+            }
+            ui.same_line();
+            if ui.radio_button_bool("TCB", matches!(curve, PathCurve::Tcb { .. }))
+                && !matches!(curve, PathCurve::Tcb { .. })
+            {
+                curve = PathCurve::Tcb {
+                    tension: 0.0,
+                    continuity: 0.0,
+                    bias: 0.0,
+                };
+            }
+            if let PathCurve::Tcb {
+                tension,
+                continuity,
+                bias,
+            } = &mut curve
+            {
+                ui.slider("Tension", -1.0, 1.0, tension);
+                ui.slider("Continuity", -1.0, 1.0, continuity);
+                ui.slider("Bias", -1.0, 1.0, bias);
+            }
+            if curve != duck.curve() {
+                duck.set_curve(curve);
+            }
+
+            let mut tint = [duck.tint.r, duck.tint.g, duck.tint.b];
+            if ui.color_edit3("Tint", &mut tint) {
+                duck.tint = Color::new(tint[0], tint[1], tint[2]);
+            }
+        }
+    }
+
+    fn route_control(&mut self, ui: &imgui::Ui) {
+        ui.text(format!(
+            "Route: {} waypoints, middle click adds one",
+            self.route.len()
+        ));
+        ui.input_text("Route file", &mut self.route_path).build();
+
+        if ui.button("Load route") {
+            match WaypointLoop::load(Path::new(&self.route_path)) {
+                Ok(route) => match route.check_bounds(self.simulation.duck_bounds()) {
+                    Ok(()) => {
+                        self.route = route.waypoints().to_vec();
+                        self.route_status = format!("Loaded {}", self.route_path);
+                    }
+                    Err(msg) => self.route_status = format!("Cannot load route: {}", msg),
+                },
+                Err(err) => self.route_status = format!("Cannot load route: {}", err),
+            }
+        }
+        ui.same_line();
+        if ui.button("Save route") {
+            self.route_status = if self.route.len() < WaypointLoop::MIN_WAYPOINTS {
+                format!(
+                    "Cannot save route: it needs at least {} waypoints, found {}",
+                    WaypointLoop::MIN_WAYPOINTS,
+                    self.route.len()
+                )
+            } else {
+                match WaypointLoop::new(self.route.clone()).save(Path::new(&self.route_path)) {
+                    Ok(()) => format!("Saved {}", self.route_path),
+                    Err(err) => format!("Cannot save route: {}", err),
+                }
+            };
+        }
+        ui.same_line();
+        if ui.button("Clear route") {
+            self.route.clear();
+        }
+
+        if !self.route_status.is_empty() {
+            ui.text(&self.route_status);
+        }
+    }
+
+    fn water_control(&mut self, ui: &imgui::Ui) {
+        ui.slider(
+            "Wave speed [m/s]",
+            0.0,
+            5.0,
+            &mut self.simulation.water_mut().wave_speed,
+        );
+
+        let mut water_rate = self.simulation.water_rate();
+        if ui.slider("Water rate [Hz]", 30.0, 480.0, &mut water_rate) {
+            self.simulation.set_water_rate(water_rate);
+        }
+
+        ui.checkbox("Interpolate water", &mut self.simulation.interpolate_water);
+
+        ui.slider("Splash radius", 0.05, 2.0, &mut self.splash_radius);
+        ui.slider("Splash strength", 0.0, 50.0, &mut self.splash_strength);
+
+        let rain = &mut self.simulation.rain;
+        ui.slider("Rain [drops/m^2/s]", 0.0, 5.0, &mut rain.intensity);
+        ui.slider("Min drop radius", 0.02, 0.5, &mut rain.min_radius);
+        ui.slider("Max drop radius", 0.02, 0.5, &mut rain.max_radius);
+        ui.slider("Drop amplitude", -2.0, 2.0, &mut rain.amplitude);
+        rain.max_radius = rain.max_radius.max(rain.min_radius);
+
+        let water = self.simulation.water();
+        ui.text(format!(
+            "Courant number: {:.3} ({} substeps)",
+            water.courant_number(),
+            water.substeps().unwrap_or(Water::MAX_SUBSTEPS)
+        ));
     }
 
     pub fn handle_event(&mut self, event: &Event<()>) {
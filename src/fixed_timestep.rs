@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+/// Accumulates frame time and converts it into a whole number of constant-length steps, so
+/// that a simulation advances at the same pace regardless of the frame rate.
+#[derive(Clone, Debug)]
+pub struct FixedTimestep {
+    rate: f32,
+    max_steps: u32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    /// `rate` is the number of steps per second and `max_steps` caps the number of steps taken
+    /// for a single frame. Time that does not fit under the cap is dropped, which prevents the
+    /// simulation from falling further behind on every slow frame.
+    pub fn new(rate: f32, max_steps: u32) -> Self {
+        assert!(rate > 0.0);
+
+        Self {
+            rate,
+            max_steps,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    pub fn set_rate(&mut self, rate: f32) {
+        assert!(rate > 0.0);
+        // The accumulated time stays the same, counted in steps of the new length
+        self.accumulator *= rate / self.rate;
+        self.rate = rate;
+    }
+
+    pub fn max_steps(&self) -> u32 {
+        self.max_steps
+    }
+
+    pub fn timestep(&self) -> f32 {
+        1.0 / self.rate
+    }
+
+    /// Adds `delta` to the accumulated time and returns how many steps should be taken now.
+    pub fn advance(&mut self, delta: Duration) -> u32 {
+        self.accumulator += delta.as_secs_f32() * self.rate;
+
+        let steps = self.accumulator.floor();
+        self.accumulator -= steps;
+
+        if steps > self.max_steps as f32 {
+            self.max_steps
+        } else {
+            steps as u32
+        }
+    }
+
+    /// Fraction of a step that is accumulated but not yet simulated, in the range `[0, 1]`.
+    /// Raising the rate can leave more than a step accumulated until the next `advance`.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.min(1.0)
+    }
+}
@@ -0,0 +1,211 @@
+use crate::math::affine::transforms;
+use nalgebra::{Matrix4, Point2, Vector2, Vector3};
+
+/// Sample of a hull where buoyancy and drag act
+#[derive(Clone, Copy, Debug)]
+pub struct HullPoint {
+    /// Offset from the center of the hull along its forward and side axes
+    pub offset: Vector2<f32>,
+    /// Part of the water plane area of the hull the point stands for
+    pub area: f32,
+}
+
+/// Rigid body floating on the water surface. Its horizontal position and heading are given from
+/// outside, while heave, pitch and roll follow from the buoyancy and drag at its hull points.
+/// All angles are small, so rotations are linearized.
+#[derive(Clone, Debug)]
+pub struct Floater {
+    hull: Vec<HullPoint>,
+    /// Depth of the hull below the waterline at rest, the hull is twice as high
+    draft: f32,
+    mass: f32,
+    /// Moments of inertia around the side (pitch) and forward (roll) axes
+    inertia: Vector2<f32>,
+    /// Drag per unit of area and vertical speed
+    drag: f32,
+
+    /// Height of the waterline above the undisturbed water plane
+    pub heave: f32,
+    /// Rotation raising the front of the hull
+    pub pitch: f32,
+    /// Rotation raising the side of the hull
+    pub roll: f32,
+    pub heave_velocity: f32,
+    pub pitch_velocity: f32,
+    pub roll_velocity: f32,
+}
+
+impl Floater {
+    pub const WATER_DENSITY: f32 = 1000.0;
+    pub const GRAVITY: f32 = 9.81;
+    /// Longest step of the integration, longer steps are subdivided
+    const MAX_TIMESTEP: f32 = 1.0 / 240.0;
+
+    /// Creates a floater at rest on flat water. Its mass is distributed like the area of the
+    /// hull points, so that it floats `draft` deep, and `damping_ratio` of its heave is critical
+    /// damping at 1.
+    pub fn new(hull: Vec<HullPoint>, draft: f32, damping_ratio: f32) -> Self {
+        assert!(!hull.is_empty() && draft > 0.0);
+
+        let area: f32 = hull.iter().map(|point| point.area).sum();
+        let mass = Self::WATER_DENSITY * area * draft;
+        let inertia = hull
+            .iter()
+            .map(|point| {
+                let offset = point.offset;
+                mass * point.area / area * Vector2::new(offset.x * offset.x, offset.y * offset.y)
+            })
+            .sum();
+
+        // Drag that gives the requested damping ratio to the heave oscillation, whose natural
+        // frequency is sqrt(g / draft)
+        let frequency = (Self::GRAVITY / draft).sqrt();
+        let drag = 2.0 * damping_ratio * mass * frequency / area;
+
+        Self {
+            hull,
+            draft,
+            mass,
+            inertia,
+            drag,
+
+            heave: 0.0,
+            pitch: 0.0,
+            roll: 0.0,
+            heave_velocity: 0.0,
+            pitch_velocity: 0.0,
+            roll_velocity: 0.0,
+        }
+    }
+
+    /// Elliptic hull of the given half length and width, sampled by a grid of
+    /// `samples` by `samples` points
+    pub fn elliptic_hull(half_length: f32, half_width: f32, samples: usize) -> Vec<HullPoint> {
+        let coords: Vec<_> = (0..samples)
+            .map(|i| (2.0 * i as f32 + 1.0) / samples as f32 - 1.0)
+            .collect();
+
+        let points: Vec<_> = coords
+            .iter()
+            .flat_map(|u| coords.iter().map(move |v| Vector2::new(*u, *v)))
+            .filter(|uv| uv.norm_squared() <= 1.0)
+            .collect();
+
+        let area = std::f32::consts::PI * half_length * half_width / points.len() as f32;
+        points
+            .into_iter()
+            .map(|uv| HullPoint {
+                offset: Vector2::new(uv.x * half_length, uv.y * half_width),
+                area,
+            })
+            .collect()
+    }
+
+    pub fn mass(&self) -> f32 {
+        self.mass
+    }
+
+    pub fn hull(&self) -> &[HullPoint] {
+        &self.hull
+    }
+
+    /// Advances the floater by `delta` seconds. The hull is centered at `position` on the water
+    /// plane and heads along `forward`, `surface` gives the height of the water at a position.
+    pub fn step(
+        &mut self,
+        delta: f32,
+        position: Point2<f32>,
+        forward: Vector2<f32>,
+        surface: impl Fn(Point2<f32>) -> f32,
+    ) {
+        let steps = (delta / Self::MAX_TIMESTEP).ceil().max(1.0);
+        let timestep = delta / steps;
+
+        // The surface only changes between calls
+        let surface_heights: Vec<_> = self
+            .hull_positions(position, forward)
+            .map(surface)
+            .collect();
+
+        for _ in 0..steps as usize {
+            self.integrate(timestep, &surface_heights);
+        }
+    }
+
+    fn integrate(&mut self, timestep: f32, surface_heights: &[f32]) {
+        let mut force = -self.mass * Self::GRAVITY;
+        let mut torque = Vector2::zeros();
+
+        for (point, surface_height) in self.hull.iter().zip(surface_heights) {
+            let submersion =
+                (surface_height - self.bottom_height(point)).clamp(0.0, 2.0 * self.draft);
+            if submersion == 0.0 {
+                continue;
+            }
+
+            let buoyancy = Self::WATER_DENSITY * Self::GRAVITY * point.area * submersion;
+            let drag = -self.drag * point.area * self.vertical_velocity(point);
+            let point_force = buoyancy + drag;
+
+            force += point_force;
+            torque += point_force * point.offset;
+        }
+
+        // Semi-implicit Euler
+        self.heave_velocity += force / self.mass * timestep;
+        self.pitch_velocity += torque.x / self.inertia.x * timestep;
+        self.roll_velocity += torque.y / self.inertia.y * timestep;
+
+        self.heave += self.heave_velocity * timestep;
+        self.pitch += self.pitch_velocity * timestep;
+        self.roll += self.roll_velocity * timestep;
+    }
+
+    fn bottom_height(&self, point: &HullPoint) -> f32 {
+        self.heave + self.pitch * point.offset.x + self.roll * point.offset.y - self.draft
+    }
+
+    fn vertical_velocity(&self, point: &HullPoint) -> f32 {
+        self.heave_velocity
+            + self.pitch_velocity * point.offset.x
+            + self.roll_velocity * point.offset.y
+    }
+
+    /// Positions of the hull points on the water plane
+    pub fn hull_positions(
+        &self,
+        position: Point2<f32>,
+        forward: Vector2<f32>,
+    ) -> impl Iterator<Item = Point2<f32>> + '_ {
+        let forward = forward.normalize();
+        let side = Vector2::new(-forward.y, forward.x);
+
+        self.hull
+            .iter()
+            .map(move |point| position + forward * point.offset.x + side * point.offset.y)
+    }
+
+    /// Volume of water per second the submerged part of the hull pushes away by moving down,
+    /// negative while it moves up
+    pub fn displacement_rate(
+        &self,
+        position: Point2<f32>,
+        forward: Vector2<f32>,
+        surface: impl Fn(Point2<f32>) -> f32,
+    ) -> f32 {
+        self.hull_positions(position, forward)
+            .zip(&self.hull)
+            .filter(|(position, point)| surface(*position) > self.bottom_height(point))
+            .map(|(_, point)| -self.vertical_velocity(point) * point.area)
+            .sum()
+    }
+
+    /// Rotation of the hull by its pitch and roll when heading along `forward`
+    pub fn orientation(&self, forward: Vector2<f32>) -> Matrix4<f32> {
+        let forward = Vector3::new(forward.x, 0.0, forward.y).normalize();
+        let side = Vector3::new(-forward.z, 0.0, forward.x);
+
+        transforms::rotate_axis(side.cross(&Vector3::y()), self.roll)
+            * transforms::rotate_axis(forward.cross(&Vector3::y()), self.pitch)
+    }
+}
@@ -0,0 +1,405 @@
+use crate::{
+    render::{
+        gl_program::GlProgram,
+        gl_texture::{GlTexture, TextureFormat},
+    },
+    shaders,
+    simulation::{Simulation, WaterEvent},
+    utils,
+    water::Water,
+    water_backend::WaterBackend,
+    water_boundary::Boundary,
+    water_disturbance::{Disturbance, DisturbancePatch},
+};
+use glow::HasContext;
+use std::ops::Range;
+
+/// GPU water backend. Keeps the heights in shader storage buffers, integrates them with the
+/// same scheme as `Water` and computes the normals in compute shaders. Only the rows the ducks
+/// float on are read back into the simulation's `Water` after a frame, the rest stays on the
+/// GPU until `read_back` is called.
+pub struct GpuWater<'gl> {
+    gl: &'gl glow::Context,
+    width: usize,
+    depth: usize,
+
+    integrate_program: GlProgram<'gl>,
+    velocity_program: GlProgram<'gl>,
+    normals_program: GlProgram<'gl>,
+    stamp_program: GlProgram<'gl>,
+
+    heights: u32,
+    previous_heights: u32,
+    speed_scales: u32,
+    land: u32,
+    normals: u32,
+    displayed_heights: u32,
+    /// Heights added by the disturbances of the last batch
+    patch: u32,
+    /// Disturbances stamped since the last step, added to the heights in one dispatch
+    pending_stamps: DisturbancePatch,
+    normal_texture: GlTexture<'gl>,
+    height_texture: GlTexture<'gl>,
+}
+
+impl<'gl> GpuWater<'gl> {
+    const WORK_GROUP_SIZE: u32 = 16;
+    const VELOCITY_WORK_GROUP_SIZE: u32 = 256;
+
+    const HEIGHTS_BINDING: u32 = 0;
+    const PREVIOUS_HEIGHTS_BINDING: u32 = 1;
+    const SPEED_SCALES_BINDING: u32 = 2;
+    const LAND_BINDING: u32 = 3;
+    const NORMALS_BINDING: u32 = 4;
+    const DISPLAYED_HEIGHTS_BINDING: u32 = 5;
+    const PATCH_BINDING: u32 = 6;
+
+    /// Makes the results of a dispatch visible to the following dispatches, buffer updates and
+    /// texture uploads
+    const BARRIERS: u32 = glow::SHADER_STORAGE_BARRIER_BIT
+        | glow::BUFFER_UPDATE_BARRIER_BIT
+        | glow::PIXEL_BUFFER_BARRIER_BIT;
+
+    /// Uploads the heights, land mask and depth profile of `water`. Later changes to the land
+    /// mask and depth profile are not picked up.
+    pub fn new(gl: &'gl glow::Context, water: &Water) -> Self {
+        let (width, depth) = (water.width(), water.depth());
+        let land: Vec<f32> = (0..depth)
+            .flat_map(|y| water.land().row(y))
+            .map(|land| if *land { 1.0 } else { 0.0 })
+            .collect();
+
+        let gpu_water = Self {
+            gl,
+            width,
+            depth,
+
+            integrate_program: shaders::create_compute_program(gl, "water_integrate_comp"),
+            velocity_program: shaders::create_compute_program(gl, "water_velocity_comp"),
+            normals_program: shaders::create_compute_program(gl, "water_normals_comp"),
+            stamp_program: shaders::create_compute_program(gl, "water_stamp_comp"),
+
+            heights: Self::create_buffer(gl, utils::slice_as_raw(water.heights())),
+            previous_heights: Self::create_buffer(
+                gl,
+                utils::slice_as_raw(water.previous_heights()),
+            ),
+            speed_scales: Self::create_buffer(
+                gl,
+                utils::slice_as_raw(water.bathymetry().speed_scales()),
+            ),
+            land: Self::create_buffer(gl, utils::slice_as_raw(&land)),
+            normals: Self::create_buffer(gl, &vec![0; 4 * 4 * width * depth]),
+            displayed_heights: Self::create_buffer(gl, &vec![0; 4 * width * depth]),
+            patch: Self::create_buffer(gl, &[]),
+            pending_stamps: DisturbancePatch::default(),
+            normal_texture: GlTexture::empty(
+                gl,
+                width as u32,
+                depth as u32,
+                TextureFormat::Rgba32F,
+            ),
+            height_texture: GlTexture::empty(gl, width as u32, depth as u32, TextureFormat::R32F),
+        };
+
+        gpu_water.compute_normals(water, 1.0);
+        gpu_water
+    }
+
+    fn create_buffer(gl: &glow::Context, data: &[u8]) -> u32 {
+        unsafe {
+            let buffer = gl
+                .create_buffer()
+                .unwrap_or_else(|msg| panic!("Failed to create GPU water buffer: {}", msg));
+            gl.bind_buffer(glow::SHADER_STORAGE_BUFFER, Some(buffer));
+            gl.buffer_data_u8_slice(glow::SHADER_STORAGE_BUFFER, data, glow::DYNAMIC_COPY);
+            gl.bind_buffer(glow::SHADER_STORAGE_BUFFER, None);
+
+            buffer
+        }
+    }
+
+    /// Replays the changes a `Simulation` recorded, see `Simulation::record_water_events`
+    pub fn replay(&mut self, water: &Water, events: &[WaterEvent]) {
+        assert_eq!((water.width(), water.depth()), (self.width, self.depth));
+
+        for event in events {
+            match event {
+                WaterEvent::Stamp(disturbance) => self.stamp(water, disturbance),
+                WaterEvent::Update => {
+                    self.flush_stamps();
+                    self.step(water);
+                }
+            }
+        }
+
+        self.flush_stamps();
+    }
+
+    /// Same as `Water::stamp`, once the stamps are flushed
+    fn stamp(&mut self, water: &Water, disturbance: &Disturbance) {
+        self.pending_stamps
+            .add(&water.disturbance_patch(disturbance));
+    }
+
+    /// Adds the heights of the pending stamps with a single upload and dispatch
+    fn flush_stamps(&mut self) {
+        let patch = std::mem::take(&mut self.pending_stamps);
+        if patch.is_empty() {
+            return;
+        }
+
+        unsafe {
+            self.gl
+                .bind_buffer(glow::SHADER_STORAGE_BUFFER, Some(self.patch));
+            self.gl.buffer_data_u8_slice(
+                glow::SHADER_STORAGE_BUFFER,
+                utils::slice_as_raw(&patch.heights),
+                glow::STREAM_DRAW,
+            );
+            self.gl.bind_buffer(glow::SHADER_STORAGE_BUFFER, None);
+        }
+
+        let program = &self.stamp_program;
+        program.enable();
+        program.uniform_i32("width", self.width as i32);
+        program.uniform_2_i32("patch_origin", patch.x.start as i32, patch.y.start as i32);
+        program.uniform_2_i32("patch_size", patch.x.len() as i32, patch.y.len() as i32);
+
+        self.bind_buffers(&[
+            (Self::HEIGHTS_BINDING, self.heights),
+            (Self::PATCH_BINDING, self.patch),
+        ]);
+
+        unsafe {
+            self.gl.dispatch_compute(
+                (patch.x.len() as u32).div_ceil(Self::WORK_GROUP_SIZE),
+                (patch.y.len() as u32).div_ceil(Self::WORK_GROUP_SIZE),
+                1,
+            );
+            self.gl.memory_barrier(Self::BARRIERS);
+        }
+    }
+
+    /// Same as `Water::update`
+    fn step(&mut self, water: &Water) {
+        let substeps = water
+            .substeps()
+            .expect("Wave speed too high for the water solver");
+        let timestep = water.timestep() / substeps as f32;
+
+        if substeps > 1 {
+            self.scale_velocity(1.0 / substeps as f32);
+        }
+
+        for _ in 0..substeps {
+            self.integrate(water, timestep);
+        }
+
+        if substeps > 1 {
+            self.scale_velocity(substeps as f32);
+        }
+    }
+
+    fn integrate(&mut self, water: &Water, timestep: f32) {
+        let program = &self.integrate_program;
+        program.enable();
+        self.grid_uniforms(program, water);
+
+        let step = water.derivative_step();
+        let c = water.wave_speed * water.wave_speed * timestep * timestep;
+        program.uniform_2_f32("a_coeff", c / (step.x * step.x), c / (step.y * step.y));
+        program.uniform_f32("timestep", timestep);
+        program.uniform_f32("wave_speed", water.wave_speed);
+        program.uniform_f32("damping_rate", water.damping_rate);
+        program.uniform_f32("sponge_strength", Water::SPONGE_STRENGTH);
+
+        let b = water.boundaries();
+        program.uniform_4_i32(
+            "sponge_widths",
+            Self::sponge_width(b.min_x),
+            Self::sponge_width(b.max_x),
+            Self::sponge_width(b.min_z),
+            Self::sponge_width(b.max_z),
+        );
+
+        self.bind_buffers(&[
+            (Self::HEIGHTS_BINDING, self.heights),
+            (Self::PREVIOUS_HEIGHTS_BINDING, self.previous_heights),
+            (Self::SPEED_SCALES_BINDING, self.speed_scales),
+            (Self::LAND_BINDING, self.land),
+        ]);
+        self.dispatch_grid();
+
+        // The previous heights have been overwritten with the next ones
+        std::mem::swap(&mut self.heights, &mut self.previous_heights);
+    }
+
+    fn scale_velocity(&self, scale: f32) {
+        let program = &self.velocity_program;
+        program.enable();
+        program.uniform_i32("sample_count", (self.width * self.depth) as i32);
+        program.uniform_f32("scale", scale);
+
+        self.bind_buffers(&[
+            (Self::HEIGHTS_BINDING, self.heights),
+            (Self::PREVIOUS_HEIGHTS_BINDING, self.previous_heights),
+        ]);
+
+        let sample_count = (self.width * self.depth) as u32;
+        unsafe {
+            self.gl
+                .dispatch_compute(sample_count.div_ceil(Self::VELOCITY_WORK_GROUP_SIZE), 1, 1);
+            self.gl.memory_barrier(Self::BARRIERS);
+        }
+    }
+
+    /// Computes the heights interpolated by `alpha` and their normals, and copies them into
+    /// the textures without leaving the GPU
+    fn compute_normals(&self, water: &Water, alpha: f32) {
+        let program = &self.normals_program;
+        program.enable();
+        self.grid_uniforms(program, water);
+        program.uniform_f32("alpha", alpha);
+
+        self.bind_buffers(&[
+            (Self::HEIGHTS_BINDING, self.heights),
+            (Self::PREVIOUS_HEIGHTS_BINDING, self.previous_heights),
+            (Self::NORMALS_BINDING, self.normals),
+            (Self::DISPLAYED_HEIGHTS_BINDING, self.displayed_heights),
+        ]);
+        self.dispatch_grid();
+
+        let (width, depth) = (self.width as u32, self.depth as u32);
+        self.normal_texture
+            .load_from_buffer(self.normals, width, depth);
+        self.height_texture
+            .load_from_buffer(self.displayed_heights, width, depth);
+    }
+
+    /// Copies all of the current and previous heights into `water`, which waits for the GPU to
+    /// finish the steps so far
+    pub fn read_back(&self, water: &mut Water) {
+        let (heights, previous_heights) = water.heights_mut();
+        self.read_rows(self.heights, heights, 0..self.depth);
+        self.read_rows(self.previous_heights, previous_heights, 0..self.depth);
+    }
+
+    /// Copies the given rows of the current heights into `water`
+    fn read_back_rows(&self, water: &mut Water, rows: &[Range<usize>]) {
+        let (heights, _) = water.heights_mut();
+        for rows in rows {
+            self.read_rows(self.heights, heights, rows.clone());
+        }
+    }
+
+    fn read_rows(&self, buffer: u32, data: &mut [f32], rows: Range<usize>) {
+        let samples = rows.start * self.width..rows.end * self.width;
+        let offset = (samples.start * std::mem::size_of::<f32>()) as i32;
+
+        unsafe {
+            self.gl
+                .bind_buffer(glow::SHADER_STORAGE_BUFFER, Some(buffer));
+            self.gl.get_buffer_sub_data(
+                glow::SHADER_STORAGE_BUFFER,
+                offset,
+                utils::slice_as_raw_mut(&mut data[samples]),
+            );
+            self.gl.bind_buffer(glow::SHADER_STORAGE_BUFFER, None);
+        }
+    }
+
+    fn grid_uniforms(&self, program: &GlProgram, water: &Water) {
+        program.uniform_i32("width", self.width as i32);
+        program.uniform_i32("depth", self.depth as i32);
+
+        let step = water.derivative_step();
+        program.uniform_2_f32("derivative_step", step.x, step.y);
+
+        let b = water.boundaries();
+        program.uniform_4_i32(
+            "boundaries",
+            Self::boundary_kind(b.min_x),
+            Self::boundary_kind(b.max_x),
+            Self::boundary_kind(b.min_z),
+            Self::boundary_kind(b.max_z),
+        );
+    }
+
+    /// Boundary constants of the compute shaders
+    fn boundary_kind(boundary: Boundary) -> i32 {
+        match boundary {
+            Boundary::Reflective => 0,
+            Boundary::Fixed => 1,
+            Boundary::Periodic => 2,
+            Boundary::Absorbing { .. } => 3,
+        }
+    }
+
+    fn sponge_width(boundary: Boundary) -> i32 {
+        match boundary {
+            Boundary::Absorbing { width } => width as i32,
+            _ => 0,
+        }
+    }
+
+    fn bind_buffers(&self, buffers: &[(u32, u32)]) {
+        for (binding, buffer) in buffers {
+            unsafe {
+                self.gl
+                    .bind_buffer_base(glow::SHADER_STORAGE_BUFFER, *binding, Some(*buffer));
+            }
+        }
+    }
+
+    fn dispatch_grid(&self) {
+        unsafe {
+            self.gl.dispatch_compute(
+                (self.width as u32).div_ceil(Self::WORK_GROUP_SIZE),
+                (self.depth as u32).div_ceil(Self::WORK_GROUP_SIZE),
+                1,
+            );
+            self.gl.memory_barrier(Self::BARRIERS);
+        }
+    }
+}
+
+impl WaterBackend for GpuWater<'_> {
+    fn update(&mut self, simulation: &mut Simulation) {
+        let events = simulation.take_water_events();
+        self.replay(simulation.water(), &events);
+
+        if !events.is_empty() {
+            let rows = simulation.duck_water_rows();
+            self.read_back_rows(simulation.water_mut(), &rows);
+        }
+
+        self.compute_normals(simulation.water(), simulation.water_alpha());
+    }
+
+    fn bind_normal_texture(&self) {
+        self.normal_texture.bind();
+    }
+
+    fn bind_height_texture(&self) {
+        self.height_texture.bind();
+    }
+}
+
+impl Drop for GpuWater<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            for buffer in [
+                self.heights,
+                self.previous_heights,
+                self.speed_scales,
+                self.land,
+                self.normals,
+                self.displayed_heights,
+                self.patch,
+            ] {
+                self.gl.delete_buffer(buffer);
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::render::texture::Texture;
+use image::imageops::FilterType;
+
+/// Marks the samples of a water grid that are covered by land
+#[derive(Clone, Debug)]
+pub struct LandMask {
+    width: usize,
+    depth: usize,
+    land: Vec<bool>,
+}
+
+impl LandMask {
+    /// Brightness above which a pixel of a mask image counts as land
+    const LAND_THRESHOLD: u8 = 127;
+
+    pub fn empty(width: usize, depth: usize) -> Self {
+        Self {
+            width,
+            depth,
+            land: vec![false; width * depth],
+        }
+    }
+
+    /// `land` is stored row by row, `width` samples per row
+    pub fn new(width: usize, depth: usize, land: Vec<bool>) -> Self {
+        assert_eq!(land.len(), width * depth);
+        Self { width, depth, land }
+    }
+
+    /// Resamples the image to the size of the grid, bright pixels are land and dark ones water
+    pub fn from_texture(texture: &Texture, width: usize, depth: usize) -> Self {
+        let image = texture
+            .image
+            .resize_exact(width as u32, depth as u32, FilterType::Triangle)
+            .into_luma8();
+
+        Self {
+            width,
+            depth,
+            land: image
+                .pixels()
+                .map(|p| p.0[0] > Self::LAND_THRESHOLD)
+                .collect(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn is_land(&self, x: usize, y: usize) -> bool {
+        self.land[self.width * y + x]
+    }
+
+    pub(crate) fn is_land_index(&self, idx: usize) -> bool {
+        self.land[idx]
+    }
+
+    pub(crate) fn row(&self, y: usize) -> &[bool] {
+        &self.land[self.width * y..self.width * (y + 1)]
+    }
+}
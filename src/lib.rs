@@ -1,13 +1,26 @@
+pub mod bathymetry;
 pub mod camera;
 pub mod constants;
+pub mod duck;
 pub mod duck_app;
+pub mod fixed_timestep;
+pub mod floater;
+pub mod gpu_water;
 pub mod keyboard;
+pub mod land_mask;
 pub mod math;
 pub mod mouse;
+pub mod path_planner;
 pub mod primitives;
+pub mod rain;
 pub mod render;
 pub mod shaders;
+pub mod simulation;
 pub mod utils;
+pub mod wake;
 pub mod water;
+pub mod water_backend;
+pub mod water_boundary;
+pub mod water_disturbance;
 pub mod water_texture;
 pub mod window;
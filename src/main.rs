@@ -1,17 +1,69 @@
-use duck::{constants::*, duck_app::DuckApp, window::Window};
+use duck::{
+    bathymetry::Bathymetry, constants::*, duck_app::DuckApp, land_mask::LandMask,
+    path_planner::WaypointLoop, render::texture::Texture, simulation::Simulation,
+    water_backend::WaterBackendKind, window::Window,
+};
 use glow::HasContext;
 use glutin::{
     event::{Event, WindowEvent},
     platform::run_return::EventLoopExtRunReturn,
 };
-use std::time::{Duration, Instant};
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
 
 fn main() {
     let (mut window, mut event_loop, gl) = Window::new(WINDOW_TITLE, WINDOW_WIDTH, WINDOW_HEIGHT);
     let mut last_frame = Instant::now();
     let mut delta = Duration::new(0, 0);
 
-    let mut duck_app = DuckApp::init(&gl);
+    let seed = arg_value("--seed")
+        .map(|seed| {
+            seed.parse()
+                .expect("--seed requires an unsigned integer argument")
+        })
+        .unwrap_or_else(rand::random);
+    println!("Using seed {}", seed);
+
+    let mut water = Simulation::default_water();
+    if let Some(path) = arg_value("--land-mask") {
+        let texture = Texture::from_file(Path::new(&path));
+        water.set_land_mask(LandMask::from_texture(
+            &texture,
+            water.width(),
+            water.depth(),
+        ));
+    }
+
+    if let Some(path) = arg_value("--depth-map") {
+        let texture = Texture::from_file(Path::new(&path));
+        water.set_bathymetry(Bathymetry::from_heightmap(
+            &texture,
+            water.width(),
+            water.depth(),
+        ));
+    }
+
+    let water_backend = arg_value("--water-backend")
+        .map(|backend| backend.parse().unwrap_or_else(|msg| panic!("{}", msg)))
+        .unwrap_or(WaterBackendKind::Cpu);
+
+    let mut simulation = Simulation::with_water(seed, water);
+    if let Some(path) = arg_value("--route") {
+        let route = WaypointLoop::load(Path::new(&path))
+            .unwrap_or_else(|err| panic!("Cannot load route {}: {}", path, err));
+        route
+            .check_bounds(simulation.duck_bounds())
+            .unwrap_or_else(|msg| panic!("Cannot follow route {}: {}", path, msg));
+        // Spread the ducks evenly over the route
+        let (count, waypoints) = (simulation.ducks().len(), route.waypoints().len());
+        for (idx, duck) in simulation.ducks_iter_mut().enumerate() {
+            duck.planner = Box::new(route.clone().starting_at(idx * waypoints / count));
+        }
+    }
+
+    let mut duck_app = DuckApp::init(&gl, simulation, water_backend);
 
     unsafe {
         gl.clear_color(CLEAR_COLOR.r, CLEAR_COLOR.g, CLEAR_COLOR.b, CLEAR_COLOR.a);
@@ -42,3 +94,23 @@ fn main() {
         }
     });
 }
+
+/// Value of a `--name value` or `--name=value` command line argument
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return Some(
+                args.next()
+                    .unwrap_or_else(|| panic!("{} requires a value", name)),
+            );
+        }
+
+        if let Some(value) = arg.strip_prefix(name).and_then(|v| v.strip_prefix('=')) {
+            return Some(value.to_owned());
+        }
+    }
+
+    None
+}
@@ -33,7 +33,7 @@ impl BernsteinPolynomial {
     }
 
     pub fn derivative(&self, t: f32) -> f32 {
-        assert!(self.coeffs.len() >= 1);
+        assert!(!self.coeffs.is_empty());
         let degree = self.coeffs.len() as f32;
 
         // This is inefficient to do on every call to `derivative`
@@ -1,76 +1,426 @@
-use super::bernstein_polynomial::BernsteinPolynomial;
+use super::geometry::curve::Curve3;
+use nalgebra::{Point3, SVector, Vector1, Vector3};
+use std::ops::{Add, Mul, Range, Sub};
 
+/// B-spline curve in `D` dimensions of any degree, with an explicit knot vector and optional
+/// rational weights (NURBS). Evaluated with de Boor's algorithm.
 #[derive(Clone, Debug)]
-pub struct CubicBSpline {
-    bernsteins: Vec<BernsteinPolynomial>,
-    deboor_points: Vec<f32>,
+pub struct BSpline<const D: usize> {
+    degree: usize,
+    knots: Vec<f32>,
+    control_points: Vec<SVector<f32, D>>,
+    /// Weights of the control points of a rational spline
+    weights: Option<Vec<f32>>,
 }
 
-impl CubicBSpline {
-    pub fn with_coefficients(deboor_points: Vec<f32>) -> Self {
+impl<const D: usize> BSpline<D> {
+    /// Spline with the knot vector `knots`, which needs `degree + 1` more knots than there are
+    /// control points
+    pub fn new(degree: usize, knots: Vec<f32>, control_points: Vec<SVector<f32, D>>) -> Self {
+        let n = control_points.len();
+        assert!(
+            n > degree,
+            "A spline of degree {} needs more than {} points",
+            degree,
+            degree
+        );
+        assert_eq!(knots.len(), n + degree + 1);
+        assert!(knots.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(knots[degree] < knots[n], "Empty spline domain");
+
         Self {
-            bernsteins: Self::as_cubic_c0(&deboor_points),
-            deboor_points,
+            degree,
+            knots,
+            control_points,
+            weights: None,
+        }
+    }
+
+    /// Spline with the knots 0, 1, 2, ..., which does not reach its first and last control
+    /// points
+    pub fn uniform(degree: usize, control_points: Vec<SVector<f32, D>>) -> Self {
+        let knots = (0..control_points.len() + degree + 1)
+            .map(|i| i as f32)
+            .collect();
+        Self::new(degree, knots, control_points)
+    }
+
+    /// Spline with uniform inner knots and `degree + 1` fold end knots, which starts at its
+    /// first and ends at its last control point
+    pub fn clamped(degree: usize, control_points: Vec<SVector<f32, D>>) -> Self {
+        let spans = control_points.len().saturating_sub(degree);
+        let knots = (0..control_points.len() + degree + 1)
+            .map(|i| i.saturating_sub(degree).min(spans) as f32)
+            .collect();
+        Self::new(degree, knots, control_points)
+    }
+
+    /// Makes the spline rational with one positive weight per control point
+    pub fn with_weights(mut self, weights: Vec<f32>) -> Self {
+        assert_eq!(weights.len(), self.control_points.len());
+        assert!(weights.iter().all(|&w| w > 0.0));
+
+        self.weights = Some(weights);
+        self
+    }
+
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    pub fn knots(&self) -> &[f32] {
+        &self.knots
+    }
+
+    pub fn control_points(&self) -> &[SVector<f32, D>] {
+        &self.control_points
+    }
+
+    pub fn weights(&self) -> Option<&[f32]> {
+        self.weights.as_deref()
+    }
+
+    /// Parameter range the curve is defined on
+    pub fn domain(&self) -> Range<f32> {
+        self.knots[self.degree]..self.knots[self.control_points.len()]
+    }
+
+    /// Number of polynomial pieces, knot spans of zero length do not count
+    pub fn segment_count(&self) -> usize {
+        let domain = self.domain();
+        self.knots
+            .windows(2)
+            .filter(|pair| pair[0] < pair[1] && pair[0] >= domain.start && pair[1] <= domain.end)
+            .count()
+    }
+
+    /// Point at the parameter `u`, which is clamped to the domain
+    pub fn value(&self, u: f32) -> SVector<f32, D> {
+        let u = self.clamp(u);
+        let span = self.span(u);
+        let args = vec![u; self.degree];
+
+        match &self.weights {
+            None => self.de_boor(span, &args, |i| self.control_points[i]),
+            Some(weights) => {
+                self.de_boor(span, &args, |i| self.control_points[i] * weights[i])
+                    / self.de_boor(span, &args, |i| weights[i])
+            }
+        }
+    }
+
+    /// Derivative of the curve with respect to `u`
+    pub fn derivative(&self, u: f32) -> SVector<f32, D> {
+        let u = self.clamp(u);
+        let span = self.span(u);
+
+        match &self.weights {
+            None => self.local_derivative(span, u, 1, |i| self.control_points[i]),
+            // Quotient rule on the weighted points divided by the weight
+            Some(weights) => {
+                let weight = self.local_derivative(span, u, 0, |i| weights[i]);
+                let weight_derivative = self.local_derivative(span, u, 1, |i| weights[i]);
+                let weighted_derivative =
+                    self.local_derivative(span, u, 1, |i| self.control_points[i] * weights[i]);
+
+                (weighted_derivative - self.value(u) * weight_derivative) / weight
+            }
+        }
+    }
+
+    /// Second derivative of the curve with respect to `u`
+    pub fn second_derivative(&self, u: f32) -> SVector<f32, D> {
+        let u = self.clamp(u);
+        let span = self.span(u);
+
+        match &self.weights {
+            None => self.local_derivative(span, u, 2, |i| self.control_points[i]),
+            // Differentiating A = w C twice gives A'' = w'' C + 2 w' C' + w C''
+            Some(weights) => {
+                let weight = self.local_derivative(span, u, 0, |i| weights[i]);
+                let weight_derivative = self.local_derivative(span, u, 1, |i| weights[i]);
+                let weight_second_derivative = self.local_derivative(span, u, 2, |i| weights[i]);
+                let weighted_second_derivative =
+                    self.local_derivative(span, u, 2, |i| self.control_points[i] * weights[i]);
+
+                (weighted_second_derivative
+                    - self.derivative(u) * (2.0 * weight_derivative)
+                    - self.value(u) * weight_second_derivative)
+                    / weight
+            }
+        }
+    }
+
+    /// Inserts the knot `u` by Boehm's algorithm, adding a control point without changing the
+    /// curve
+    pub fn insert_knot(&mut self, u: f32) {
+        let u = self.clamp(u);
+        let span = self.span(u);
+        let p = self.degree;
+        let weights = self
+            .weights
+            .clone()
+            .unwrap_or_else(|| vec![1.0; self.control_points.len()]);
+
+        // Inserted on homogeneous coordinates, so that rational splines keep their shape
+        let mut points = Vec::with_capacity(self.control_points.len() + 1);
+        let mut new_weights = Vec::with_capacity(self.control_points.len() + 1);
+        for i in 0..=self.control_points.len() {
+            let alpha = if i + p <= span {
+                1.0
+            } else if i > span {
+                0.0
+            } else {
+                (u - self.knots[i]) / (self.knots[i + p] - self.knots[i])
+            };
+
+            let weighted = |j: usize| self.control_points[j] * weights[j];
+            let (point, weight) = if alpha == 1.0 {
+                (weighted(i), weights[i])
+            } else if alpha == 0.0 {
+                (weighted(i - 1), weights[i - 1])
+            } else {
+                (
+                    weighted(i - 1) * (1.0 - alpha) + weighted(i) * alpha,
+                    weights[i - 1] * (1.0 - alpha) + weights[i] * alpha,
+                )
+            };
+
+            points.push(point / weight);
+            new_weights.push(weight);
+        }
+
+        self.knots.insert(span + 1, u);
+        self.control_points = points;
+        if self.weights.is_some() {
+            self.weights = Some(new_weights);
+        }
+    }
+
+    /// Control points of the Bézier segments of a polynomial spline, consecutive segments
+    /// share their end points
+    pub fn bezier_points(&self) -> Vec<SVector<f32, D>> {
+        assert!(
+            self.weights.is_none(),
+            "Bézier points of a rational spline need weights"
+        );
+
+        let p = self.degree;
+        let mut points = Vec::new();
+
+        for span in p..self.control_points.len() {
+            let (start, end) = (self.knots[span], self.knots[span + 1]);
+            if start >= end {
+                continue;
+            }
+
+            // The Bézier points of a segment are its blossoms at the segment ends
+            let first = if points.is_empty() { 0 } else { 1 };
+            for j in first..=p {
+                let mut args = vec![start; p - j];
+                args.extend(std::iter::repeat_n(end, j));
+                points.push(self.de_boor(span, &args, |i| self.control_points[i]));
+            }
         }
+
+        points
+    }
+
+    fn clamp(&self, u: f32) -> f32 {
+        let domain = self.domain();
+        u.clamp(domain.start, domain.end)
     }
 
-    fn as_cubic_c0(deboor_points: &[f32]) -> Vec<BernsteinPolynomial> {
-        let mut bernsteins = Vec::new();
+    /// Index `k` of the knot span `knots[k]..knots[k + 1]` of non-zero length that contains
+    /// `u`, the last span for the end of the domain
+    fn span(&self, u: f32) -> usize {
+        let last = self.control_points.len() - 1;
+        let mut span = self
+            .knots
+            .partition_point(|&knot| knot <= u)
+            .saturating_sub(1)
+            .clamp(self.degree, last);
 
-        for i in 0..deboor_points.len() - 1 {
-            bernsteins.push(BernsteinPolynomial::with_coefficients(vec![
-                0.0,
-                (2.0 * deboor_points[i] + deboor_points[i + 1]) / 3.0,
-                (deboor_points[i] + 2.0 * deboor_points[i + 1]) / 3.0,
-                0.0,
-            ]));
+        while span > self.degree && self.knots[span] >= self.knots[span + 1] {
+            span -= 1;
         }
 
-        for i in 1..deboor_points.len() - 2 {
-            bernsteins[i].coeffs[0] = (bernsteins[i - 1].coeffs[2] + bernsteins[i].coeffs[1]) * 0.5;
-            bernsteins[i].coeffs[3] = (bernsteins[i].coeffs[2] + bernsteins[i + 1].coeffs[1]) * 0.5;
+        span
+    }
+
+    /// De Boor's algorithm on the span `span`, with the coefficients given by `coefficient`.
+    /// Level `r` of the recursion uses `args[r - 1]`, which gives the blossom of the spline
+    /// and its value when all of them are equal.
+    fn de_boor<T>(&self, span: usize, args: &[f32], coefficient: impl Fn(usize) -> T) -> T
+    where
+        T: Copy + Add<Output = T> + Mul<f32, Output = T>,
+    {
+        let p = self.degree;
+        let coefficients = (0..=p).map(|j| coefficient(j + span - p)).collect();
+
+        de_boor(&self.knots, p, span, coefficients, args)
+    }
+
+    /// Derivative of the given order at `u` in `span` of the non-rational spline with the
+    /// coefficients given by `coefficient`
+    fn local_derivative<T>(
+        &self,
+        span: usize,
+        u: f32,
+        order: usize,
+        coefficient: impl Fn(usize) -> T,
+    ) -> T
+    where
+        T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T>,
+    {
+        let (mut p, mut span, mut knots) = (self.degree, span, &self.knots[..]);
+        let mut coefficients: Vec<_> = (0..=p).map(|j| coefficient(j + span - p)).collect();
+
+        for _ in 0..order {
+            if p == 0 {
+                return coefficients[0] * 0.0;
+            }
+
+            // The derivative is a spline of degree p - 1 on the knots without the first and
+            // last
+            coefficients = (0..p)
+                .map(|j| {
+                    let i = span - p + j;
+                    let scale = p as f32 / (knots[i + p + 1] - knots[i + 1]);
+                    (coefficients[j + 1] - coefficients[j]) * scale
+                })
+                .collect();
+
+            knots = &knots[1..knots.len() - 1];
+            span -= 1;
+            p -= 1;
         }
 
-        bernsteins[1..deboor_points.len() - 2].to_vec()
+        de_boor(knots, p, span, coefficients, &vec![u; p])
+    }
+}
+
+impl Curve3 for BSpline<3> {
+    fn domain(&self) -> Range<f32> {
+        BSpline::domain(self)
+    }
+
+    fn value(&self, t: f32) -> Point3<f32> {
+        Point3::from(BSpline::value(self, t))
+    }
+
+    fn derivative(&self, t: f32) -> Vector3<f32> {
+        BSpline::derivative(self, t)
+    }
+
+    fn second_derivative(&self, t: f32) -> Vector3<f32> {
+        BSpline::second_derivative(self, t)
+    }
+
+    /// The distinct knots within the domain
+    fn breakpoints(&self) -> Vec<f32> {
+        let domain = BSpline::domain(self);
+        let mut breakpoints: Vec<_> = self
+            .knots
+            .iter()
+            .copied()
+            .filter(|knot| domain.contains(knot) || *knot == domain.end)
+            .collect();
+        breakpoints.dedup();
+        breakpoints
+    }
+
+    fn clone_box(&self) -> Box<dyn Curve3> {
+        Box::new(self.clone())
     }
+}
+
+/// De Boor's algorithm on the `degree + 1` coefficients of the knot span `span`
+fn de_boor<T>(
+    knots: &[f32],
+    degree: usize,
+    span: usize,
+    mut coefficients: Vec<T>,
+    args: &[f32],
+) -> T
+where
+    T: Copy + Add<Output = T> + Mul<f32, Output = T>,
+{
+    for r in 1..=degree {
+        let u = args[r - 1];
+
+        for j in (r..=degree).rev() {
+            let i = j + span - degree;
+            let width = knots[i + degree + 1 - r] - knots[i];
+            let alpha = if width > 0.0 {
+                (u - knots[i]) / width
+            } else {
+                0.0
+            };
 
-    fn curve_idx(&self, t: f32) -> usize {
-        if t == 1.0 {
-            self.bernsteins.len() - 1
-        } else {
-            (t * self.bernsteins.len() as f32).floor() as usize
+            coefficients[j] = coefficients[j - 1] * (1.0 - alpha) + coefficients[j] * alpha;
         }
     }
 
-    fn curve_t(&self, t: f32, curve_idx: usize) -> f32 {
-        self.bernsteins.len() as f32 * t - curve_idx as f32
+    coefficients[degree]
+}
+
+/// Uniform cubic B-spline with the parameter range [0, 1], each segment spans an equal part of
+/// it. Fewer than four de Boor points give a spline of lower degree.
+#[derive(Clone, Debug)]
+pub struct CubicBSpline {
+    spline: BSpline<1>,
+}
+
+impl CubicBSpline {
+    pub fn with_coefficients(deboor_points: Vec<f32>) -> Self {
+        assert!(!deboor_points.is_empty());
+
+        let degree = (deboor_points.len() - 1).min(3);
+        let segments = (deboor_points.len() - degree) as f32;
+        let knots = (0..deboor_points.len() + degree + 1)
+            .map(|i| (i as f32 - degree as f32) / segments)
+            .collect();
+
+        Self {
+            spline: BSpline::new(
+                degree,
+                knots,
+                deboor_points.into_iter().map(Vector1::new).collect(),
+            ),
+        }
     }
 
     pub fn value(&self, t: f32) -> f32 {
-        let curve_idx = self.curve_idx(t);
-        let curve_t = self.curve_t(t, curve_idx);
-        self.bernsteins[curve_idx].value(curve_t)
+        self.spline.value(t).x
     }
 
     pub fn derivative(&self, t: f32) -> f32 {
-        let curve_idx = self.curve_idx(t);
-        let curve_t = self.curve_t(t, curve_idx);
-        self.bernsteins[curve_idx].derivative(curve_t)
+        self.spline.derivative(t).x
     }
 
-    pub fn bernstein_values(&self) -> Vec<f32> {
-        let mut vals = Vec::new();
-        for bernstein in &self.bernsteins {
-            vals.push(bernstein.coeffs[0]);
-            vals.push(bernstein.coeffs[1]);
-            vals.push(bernstein.coeffs[2]);
-        }
+    pub fn second_derivative(&self, t: f32) -> f32 {
+        self.spline.second_derivative(t).x
+    }
 
-        vals.push(self.bernsteins.last().unwrap().coeffs[3]);
-        vals
+    /// Number of segments, each of which spans an equal part of the parameter range
+    pub fn segment_count(&self) -> usize {
+        self.spline.segment_count()
+    }
+
+    pub fn bernstein_values(&self) -> Vec<f32> {
+        self.spline
+            .bezier_points()
+            .into_iter()
+            .map(|point| point.x)
+            .collect()
     }
 
     pub fn deboor_points(&self) -> Vec<f32> {
-        self.deboor_points.clone()
+        self.spline
+            .control_points()
+            .iter()
+            .map(|point| point.x)
+            .collect()
     }
 }
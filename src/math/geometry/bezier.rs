@@ -1,5 +1,7 @@
+use super::curve::Curve3;
 use crate::math::bspline::CubicBSpline;
 use nalgebra::{Point3, Vector3};
+use std::ops::Range;
 
 #[derive(Clone, Debug)]
 pub struct BezierBSpline {
@@ -10,7 +12,7 @@ pub struct BezierBSpline {
 
 impl BezierBSpline {
     pub fn through_points(points: Vec<Point3<f32>>) -> Self {
-        assert!(points.len() >= 4);
+        assert!(!points.is_empty());
 
         Self {
             x_t: CubicBSpline::with_coefficients(points.iter().map(|p| p.x).collect()),
@@ -45,16 +47,118 @@ impl BezierBSpline {
         deboor
     }
 
-    pub fn value(&self, t: f32) -> Point3<f32> {
+    pub fn segment_count(&self) -> usize {
+        self.x_t.segment_count()
+    }
+}
+
+impl Curve3 for BezierBSpline {
+    fn domain(&self) -> Range<f32> {
+        0.0..1.0
+    }
+
+    fn value(&self, t: f32) -> Point3<f32> {
         Point3::new(self.x_t.value(t), self.y_t.value(t), self.z_t.value(t))
     }
 
-    pub fn tangent(&self, t: f32) -> Vector3<f32> {
+    fn derivative(&self, t: f32) -> Vector3<f32> {
         Vector3::new(
             self.x_t.derivative(t),
             self.y_t.derivative(t),
             self.z_t.derivative(t),
         )
-        .normalize()
+    }
+
+    fn second_derivative(&self, t: f32) -> Vector3<f32> {
+        Vector3::new(
+            self.x_t.second_derivative(t),
+            self.y_t.second_derivative(t),
+            self.z_t.second_derivative(t),
+        )
+    }
+
+    fn breakpoints(&self) -> Vec<f32> {
+        let segments = self.segment_count();
+        (0..=segments).map(|i| i as f32 / segments as f32).collect()
+    }
+
+    fn clone_box(&self) -> Box<dyn Curve3> {
+        Box::new(self.clone())
+    }
+}
+
+/// Cubic Bézier segments joined end to end. Segment `i` has the control points `3i` to `3i + 3`
+/// and spans the parameters from `i` to `i + 1`.
+#[derive(Clone, Debug)]
+pub struct BezierChain {
+    points: Vec<Point3<f32>>,
+}
+
+impl BezierChain {
+    pub fn new(points: Vec<Point3<f32>>) -> Self {
+        assert!(
+            points.len() >= 4 && (points.len() - 1).is_multiple_of(3),
+            "A chain of cubic segments needs 3n + 1 points"
+        );
+
+        Self { points }
+    }
+
+    pub fn points(&self) -> &[Point3<f32>] {
+        &self.points
+    }
+
+    pub fn segment_count(&self) -> usize {
+        (self.points.len() - 1) / 3
+    }
+
+    /// Control points of the segment containing `t` and the parameter within it
+    fn segment(&self, t: f32) -> (&[Point3<f32>], f32) {
+        let last = self.segment_count() - 1;
+        let t = t.clamp(0.0, last as f32 + 1.0);
+        let i = (t as usize).min(last);
+
+        (&self.points[3 * i..3 * i + 4], t - i as f32)
+    }
+}
+
+impl Curve3 for BezierChain {
+    fn domain(&self) -> Range<f32> {
+        0.0..self.segment_count() as f32
+    }
+
+    fn value(&self, t: f32) -> Point3<f32> {
+        let (p, s) = self.segment(t);
+        let r = 1.0 - s;
+
+        Point3::from(
+            p[0].coords * (r * r * r)
+                + p[1].coords * (3.0 * r * r * s)
+                + p[2].coords * (3.0 * r * s * s)
+                + p[3].coords * (s * s * s),
+        )
+    }
+
+    fn derivative(&self, t: f32) -> Vector3<f32> {
+        let (p, s) = self.segment(t);
+        let r = 1.0 - s;
+
+        ((p[1] - p[0]) * (r * r) + (p[2] - p[1]) * (2.0 * r * s) + (p[3] - p[2]) * (s * s)) * 3.0
+    }
+
+    fn second_derivative(&self, t: f32) -> Vector3<f32> {
+        let (p, s) = self.segment(t);
+        let start = (p[2] - p[1]) - (p[1] - p[0]);
+        let end = (p[3] - p[2]) - (p[2] - p[1]);
+
+        (start * (1.0 - s) + end * s) * 6.0
+    }
+
+    fn breakpoints(&self) -> Vec<f32> {
+        (0..=self.segment_count()).map(|i| i as f32).collect()
+    }
+
+    fn clone_box(&self) -> Box<dyn Curve3> {
+        Box::new(self.clone())
     }
 }
@@ -0,0 +1,208 @@
+use nalgebra::{Point3, Vector3};
+use std::{fmt, ops::Range};
+
+/// Axis aligned box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl BoundingBox {
+    pub fn new(min: Point3<f32>, max: Point3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    /// Smallest box containing `point`
+    pub fn point(point: Point3<f32>) -> Self {
+        Self::new(point, point)
+    }
+
+    /// Grows the box to contain `point`
+    pub fn extend(&mut self, point: Point3<f32>) {
+        self.min = self.min.inf(&point);
+        self.max = self.max.sup(&point);
+    }
+
+    pub fn contains(&self, point: &Point3<f32>) -> bool {
+        (0..3).all(|i| self.min[i] <= point[i] && point[i] <= self.max[i])
+    }
+}
+
+/// Parametric curve in space. Arc length and bounding box are computed from the derivatives
+/// piece by piece, between the `breakpoints` at which the curve may lose smoothness.
+pub trait Curve3: fmt::Debug {
+    /// Parameter range the curve is defined on
+    fn domain(&self) -> Range<f32>;
+
+    fn value(&self, t: f32) -> Point3<f32>;
+
+    /// Derivative of the position with respect to `t`
+    fn derivative(&self, t: f32) -> Vector3<f32>;
+
+    fn second_derivative(&self, t: f32) -> Vector3<f32>;
+
+    /// Increasing parameters at which the smooth pieces of the curve meet, starting and ending
+    /// with the ends of the domain
+    fn breakpoints(&self) -> Vec<f32>;
+
+    fn clone_box(&self) -> Box<dyn Curve3>;
+
+    fn tangent(&self, t: f32) -> Vector3<f32> {
+        self.derivative(t).normalize()
+    }
+
+    /// Length of the whole curve
+    fn length(&self) -> f32 {
+        self.arc_length(self.domain().end)
+    }
+
+    /// Length of the curve from its start to the parameter `t`
+    fn arc_length(&self, t: f32) -> f32 {
+        let domain = self.domain();
+        let t = t.clamp(domain.start, domain.end);
+
+        self.breakpoints()
+            .windows(2)
+            .take_while(|piece| piece[0] < t)
+            .map(|piece| {
+                let end = piece[1].min(t);
+                let step = (piece[1] - piece[0]) / LENGTH_SUBDIVISIONS as f32;
+
+                (0..LENGTH_SUBDIVISIONS)
+                    .map(|i| piece[0] + i as f32 * step)
+                    .take_while(|&start| start < end)
+                    .map(|start| interval_length(self, start, (start + step).min(end)))
+                    .sum::<f32>()
+            })
+            .sum()
+    }
+
+    /// Parameter at which the curve reaches the given length from its start. Inverts
+    /// `arc_length` by Newton's method, falling back to bisection where a Newton step would
+    /// leave the bracket of the solution.
+    fn parameter_at_length(&self, length: f32) -> f32 {
+        let domain = self.domain();
+        let total = self.length();
+        if length <= 0.0 || total <= 0.0 {
+            return domain.start;
+        } else if length >= total {
+            return domain.end;
+        }
+
+        let (mut low, mut high) = (domain.start, domain.end);
+        let mut t = domain.start + (domain.end - domain.start) * length / total;
+
+        for _ in 0..MAX_NEWTON_ITERATIONS {
+            let error = self.arc_length(t) - length;
+            if error.abs() <= LENGTH_TOLERANCE * total {
+                break;
+            }
+
+            if error > 0.0 {
+                high = t;
+            } else {
+                low = t;
+            }
+
+            let next = t - error / self.derivative(t).norm();
+            t = if next > low && next < high {
+                next
+            } else {
+                0.5 * (low + high)
+            };
+        }
+
+        t
+    }
+
+    /// Smallest axis aligned box containing the curve. The extrema inside a piece are found
+    /// where a coordinate of the derivative changes its sign between samples.
+    fn bounding_box(&self) -> BoundingBox {
+        let breakpoints = self.breakpoints();
+        let mut bounds = BoundingBox::point(self.value(breakpoints[0]));
+
+        for piece in breakpoints.windows(2) {
+            bounds.extend(self.value(piece[1]));
+
+            let step = (piece[1] - piece[0]) / EXTREMUM_SAMPLES as f32;
+            for i in 0..EXTREMUM_SAMPLES {
+                let (start, end) = (piece[0] + i as f32 * step, piece[0] + (i + 1) as f32 * step);
+                bounds.extend(self.value(start));
+
+                for axis in 0..3 {
+                    let (a, b) = (self.derivative(start)[axis], self.derivative(end)[axis]);
+                    if a * b < 0.0 {
+                        bounds.extend(self.value(extremum(self, axis, start, end)));
+                    }
+                }
+            }
+        }
+
+        bounds
+    }
+}
+
+impl Clone for Box<dyn Curve3> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Nodes and weights of the five-point Gauss-Legendre rule on [-1, 1]
+const GAUSS_LEGENDRE: [(f32, f32); 5] = [
+    (0.0, 0.568_888_9),
+    (-0.538_469_3, 0.478_628_67),
+    (0.538_469_3, 0.478_628_67),
+    (-0.906_179_85, 0.236_926_88),
+    (0.906_179_85, 0.236_926_88),
+];
+/// Intervals every piece is split into for integrating its length
+const LENGTH_SUBDIVISIONS: usize = 4;
+const MAX_NEWTON_ITERATIONS: usize = 20;
+/// Accepted error of `parameter_at_length`, relative to the length of the curve
+const LENGTH_TOLERANCE: f32 = 1.0e-5;
+/// Samples of the derivative per piece when looking for extrema
+const EXTREMUM_SAMPLES: usize = 8;
+const EXTREMUM_ITERATIONS: usize = 30;
+
+/// Integrates the speed over a parameter interval within one piece of `curve`
+fn interval_length<C: Curve3 + ?Sized>(curve: &C, start: f32, end: f32) -> f32 {
+    let half = 0.5 * (end - start);
+    let center = start + half;
+
+    half * GAUSS_LEGENDRE
+        .iter()
+        .map(|(node, weight)| weight * curve.derivative(center + half * node).norm())
+        .sum::<f32>()
+}
+
+/// Parameter between `start` and `end` at which the coordinate `axis` of the derivative of
+/// `curve`, which has different signs at both, vanishes. Newton steps on the second derivative
+/// are safeguarded by bisection.
+fn extremum<C: Curve3 + ?Sized>(curve: &C, axis: usize, mut start: f32, mut end: f32) -> f32 {
+    let start_sign = curve.derivative(start)[axis].signum();
+    let mut t = 0.5 * (start + end);
+
+    for _ in 0..EXTREMUM_ITERATIONS {
+        let derivative = curve.derivative(t)[axis];
+        if derivative == 0.0 {
+            break;
+        }
+
+        if derivative.signum() == start_sign {
+            start = t;
+        } else {
+            end = t;
+        }
+
+        let next = t - derivative / curve.second_derivative(t)[axis];
+        t = if next > start && next < end {
+            next
+        } else {
+            0.5 * (start + end)
+        };
+    }
+
+    t
+}
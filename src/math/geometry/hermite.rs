@@ -0,0 +1,234 @@
+use super::curve::{BoundingBox, Curve3};
+use nalgebra::{Point3, Vector3};
+use std::ops::Range;
+
+/// Cubic Hermite segments through `points`, segment `i` spans the parameters from `i` to
+/// `i + 1`. Every point has an `outgoing` tangent, with which the segment starting there leaves
+/// it, and an `incoming` tangent, with which the segment ending there arrives. The curve is C1
+/// where both are the same.
+#[derive(Clone, Debug)]
+pub struct HermiteSpline {
+    points: Vec<Point3<f32>>,
+    outgoing: Vec<Vector3<f32>>,
+    incoming: Vec<Vector3<f32>>,
+}
+
+impl HermiteSpline {
+    /// Exponent of the distances between the points that gives the centripetal Catmull-Rom
+    /// spline, which neither forms cusps nor loops within a segment
+    const CENTRIPETAL: f32 = 0.5;
+    /// Smallest parameter step of the Catmull-Rom spline, so that repeated points do not divide
+    /// by zero
+    const MIN_STEP: f32 = 1.0e-4;
+
+    pub fn new(
+        points: Vec<Point3<f32>>,
+        outgoing: Vec<Vector3<f32>>,
+        incoming: Vec<Vector3<f32>>,
+    ) -> Self {
+        assert!(points.len() >= 2, "A Hermite spline needs two points");
+        assert_eq!(outgoing.len(), points.len());
+        assert_eq!(incoming.len(), points.len());
+
+        Self {
+            points,
+            outgoing,
+            incoming,
+        }
+    }
+
+    /// Centripetal Catmull-Rom spline, which passes through all but the first and the last of
+    /// `points`. Those only steer the tangents at the ends.
+    pub fn catmull_rom(points: Vec<Point3<f32>>) -> Self {
+        assert!(points.len() >= 4, "A Catmull-Rom spline needs four points");
+
+        // Parameters of the points spaced by the square roots of their distances
+        let steps: Vec<_> = points
+            .windows(2)
+            .map(|pair| {
+                (pair[1] - pair[0])
+                    .norm()
+                    .powf(Self::CENTRIPETAL)
+                    .max(Self::MIN_STEP)
+            })
+            .collect();
+
+        // Tangents with respect to those parameters
+        let tangents: Vec<_> = (1..points.len() - 1)
+            .map(|i| {
+                let (before, after) = (steps[i - 1], steps[i]);
+                (points[i] - points[i - 1]) / before
+                    - (points[i + 1] - points[i - 1]) / (before + after)
+                    + (points[i + 1] - points[i]) / after
+            })
+            .collect();
+
+        // Every segment is reparameterized to unit length
+        let outgoing = (1..points.len() - 1)
+            .map(|i| tangents[i - 1] * steps[i])
+            .collect();
+        let incoming = (1..points.len() - 1)
+            .map(|i| tangents[i - 1] * steps[i - 1])
+            .collect();
+
+        Self::new(points[1..points.len() - 1].to_vec(), outgoing, incoming)
+    }
+
+    /// Kochanek-Bartels spline, which passes through all but the first and the last of `points`.
+    /// Positive `tension` tightens the curve at the points, `continuity` away from 0 gives
+    /// corners and `bias` leans the tangents towards the next (positive) or previous (negative)
+    /// point. All three 0 give the uniform Catmull-Rom spline.
+    pub fn kochanek_bartels(
+        points: Vec<Point3<f32>>,
+        tension: f32,
+        continuity: f32,
+        bias: f32,
+    ) -> Self {
+        assert!(
+            points.len() >= 4,
+            "A Kochanek-Bartels spline needs four points"
+        );
+
+        let (t, c, b) = (tension, continuity, bias);
+        let tangent = |i: usize, before: f32, after: f32| {
+            (points[i] - points[i - 1]) * (0.5 * (1.0 - t) * (1.0 + b) * before)
+                + (points[i + 1] - points[i]) * (0.5 * (1.0 - t) * (1.0 - b) * after)
+        };
+
+        let outgoing = (1..points.len() - 1)
+            .map(|i| tangent(i, 1.0 + c, 1.0 - c))
+            .collect();
+        let incoming = (1..points.len() - 1)
+            .map(|i| tangent(i, 1.0 - c, 1.0 + c))
+            .collect();
+
+        Self::new(points[1..points.len() - 1].to_vec(), outgoing, incoming)
+    }
+
+    pub fn points(&self) -> &[Point3<f32>] {
+        &self.points
+    }
+
+    /// Shortens the tangents so that the curve stays within `bounds`, which has to contain the
+    /// points. Both tangents of a point are shortened by the same factor, which keeps their
+    /// directions and the curve C1 where it was.
+    pub fn confine(&mut self, bounds: &BoundingBox) {
+        // A segment lies within the convex hull of its Bézier points p, p + m / 3, q - m' / 3, q
+        let last = self.points.len() - 1;
+        for i in 0..=last {
+            let mut scale: f32 = 1.0;
+            if i < last {
+                scale = scale.min(Self::room(
+                    &self.points[i],
+                    &(self.outgoing[i] / 3.0),
+                    bounds,
+                ));
+            }
+            if i > 0 {
+                scale = scale.min(Self::room(
+                    &self.points[i],
+                    &(-self.incoming[i] / 3.0),
+                    bounds,
+                ));
+            }
+
+            self.outgoing[i] *= scale;
+            self.incoming[i] *= scale;
+        }
+    }
+
+    /// Largest factor up to 1 by which `offset` can be scaled with `point + offset` staying
+    /// within `bounds`
+    fn room(point: &Point3<f32>, offset: &Vector3<f32>, bounds: &BoundingBox) -> f32 {
+        (0..3).fold(1.0, |scale, i| {
+            let limit = if offset[i] > 0.0 {
+                bounds.max[i] - point[i]
+            } else if offset[i] < 0.0 {
+                bounds.min[i] - point[i]
+            } else {
+                return scale;
+            };
+
+            scale.min((limit / offset[i]).max(0.0))
+        })
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.points.len() - 1
+    }
+
+    /// Index of the segment containing `t` and the parameter within it
+    fn segment(&self, t: f32) -> (usize, f32) {
+        let last = self.segment_count() - 1;
+        let t = t.clamp(0.0, last as f32 + 1.0);
+        let i = (t as usize).min(last);
+
+        (i, t - i as f32)
+    }
+
+    /// Combines the ends and the tangents of segment `i` with the given weights
+    fn combine(&self, i: usize, weights: [f32; 4]) -> Vector3<f32> {
+        self.points[i].coords * weights[0]
+            + self.outgoing[i] * weights[1]
+            + self.points[i + 1].coords * weights[2]
+            + self.incoming[i + 1] * weights[3]
+    }
+}
+
+impl Curve3 for HermiteSpline {
+    fn domain(&self) -> Range<f32> {
+        0.0..self.segment_count() as f32
+    }
+
+    fn value(&self, t: f32) -> Point3<f32> {
+        let (i, s) = self.segment(t);
+        let (s2, s3) = (s * s, s * s * s);
+
+        Point3::from(self.combine(
+            i,
+            [
+                2.0 * s3 - 3.0 * s2 + 1.0,
+                s3 - 2.0 * s2 + s,
+                -2.0 * s3 + 3.0 * s2,
+                s3 - s2,
+            ],
+        ))
+    }
+
+    fn derivative(&self, t: f32) -> Vector3<f32> {
+        let (i, s) = self.segment(t);
+        let s2 = s * s;
+
+        self.combine(
+            i,
+            [
+                6.0 * s2 - 6.0 * s,
+                3.0 * s2 - 4.0 * s + 1.0,
+                -6.0 * s2 + 6.0 * s,
+                3.0 * s2 - 2.0 * s,
+            ],
+        )
+    }
+
+    fn second_derivative(&self, t: f32) -> Vector3<f32> {
+        let (i, s) = self.segment(t);
+
+        self.combine(
+            i,
+            [
+                12.0 * s - 6.0,
+                6.0 * s - 4.0,
+                -12.0 * s + 6.0,
+                6.0 * s - 2.0,
+            ],
+        )
+    }
+
+    fn breakpoints(&self) -> Vec<f32> {
+        (0..=self.segment_count()).map(|i| i as f32).collect()
+    }
+
+    fn clone_box(&self) -> Box<dyn Curve3> {
+        Box::new(self.clone())
+    }
+}
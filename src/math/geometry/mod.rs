@@ -1 +1,4 @@
 pub mod bezier;
+pub mod curve;
+pub mod hermite;
+pub mod ray;
@@ -0,0 +1,29 @@
+use nalgebra::{Point3, Vector3};
+
+/// Half-line starting at `origin`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Point3<f32>,
+    /// Unit direction
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    pub fn new(origin: Point3<f32>, direction: Vector3<f32>) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    pub fn point(&self, distance: f32) -> Point3<f32> {
+        self.origin + distance * self.direction
+    }
+
+    /// Point where the ray crosses the horizontal plane at height `y`, `None` if the ray runs
+    /// parallel to the plane or away from it
+    pub fn intersect_horizontal_plane(&self, y: f32) -> Option<Point3<f32>> {
+        let distance = (y - self.origin.y) / self.direction.y;
+        (distance.is_finite() && distance >= 0.0).then(|| self.point(distance))
+    }
+}
@@ -0,0 +1,251 @@
+use super::decompositions::tridiagonal;
+use nalgebra::SVector;
+
+/// How an interpolating spline behaves at its ends
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EndCondition<const D: usize> {
+    /// The second derivative vanishes at both ends
+    Natural,
+    /// The first derivative at the start and at the end are given
+    Clamped(SVector<f32, D>, SVector<f32, D>),
+    /// The curve closes on itself with continuous first and second derivatives
+    Periodic,
+}
+
+/// C2 cubic spline in `D` dimensions that passes through its points at the given parameters.
+/// Stored by the second derivatives at the points, which solve a tridiagonal system.
+#[derive(Clone, Debug)]
+pub struct InterpolatingSpline<const D: usize> {
+    parameters: Vec<f32>,
+    /// Points at the parameters, a periodic spline repeats its first point at the end
+    points: Vec<SVector<f32, D>>,
+    second_derivatives: Vec<SVector<f32, D>>,
+    periodic: bool,
+}
+
+impl<const D: usize> InterpolatingSpline<D> {
+    /// Spline through `points` at the increasing `parameters`. A periodic spline takes one
+    /// parameter more than points, at which it is back at the first point.
+    pub fn new(
+        parameters: Vec<f32>,
+        mut points: Vec<SVector<f32, D>>,
+        end: EndCondition<D>,
+    ) -> Self {
+        let periodic = end == EndCondition::Periodic;
+        if periodic {
+            assert!(points.len() >= 3, "A periodic spline needs three points");
+            points.push(points[0]);
+        } else {
+            assert!(points.len() >= 2, "A spline needs two points");
+        }
+
+        assert_eq!(parameters.len(), points.len());
+        assert!(parameters.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let second_derivatives = Self::second_derivatives(&parameters, &points, end);
+
+        Self {
+            parameters,
+            points,
+            second_derivatives,
+            periodic,
+        }
+    }
+
+    /// Spline through `points` at the parameters 0, 1, 2, ...
+    pub fn uniform(points: Vec<SVector<f32, D>>, end: EndCondition<D>) -> Self {
+        let count = points.len() + usize::from(end == EndCondition::Periodic);
+        Self::new((0..count).map(|i| i as f32).collect(), points, end)
+    }
+
+    pub fn parameters(&self) -> &[f32] {
+        &self.parameters
+    }
+
+    /// Interpolated points, including the repeated first point of a periodic spline
+    pub fn points(&self) -> &[SVector<f32, D>] {
+        &self.points
+    }
+
+    pub fn is_periodic(&self) -> bool {
+        self.periodic
+    }
+
+    pub fn value(&self, t: f32) -> SVector<f32, D> {
+        let (i, t) = self.interval(t);
+        let h = self.parameters[i + 1] - self.parameters[i];
+        let (a, b) = (self.parameters[i + 1] - t, t - self.parameters[i]);
+        let (m0, m1) = (self.second_derivatives[i], self.second_derivatives[i + 1]);
+
+        m0 * (a * a * a / (6.0 * h))
+            + m1 * (b * b * b / (6.0 * h))
+            + (self.points[i] - m0 * (h * h / 6.0)) * (a / h)
+            + (self.points[i + 1] - m1 * (h * h / 6.0)) * (b / h)
+    }
+
+    pub fn derivative(&self, t: f32) -> SVector<f32, D> {
+        let (i, t) = self.interval(t);
+        let h = self.parameters[i + 1] - self.parameters[i];
+        let (a, b) = (self.parameters[i + 1] - t, t - self.parameters[i]);
+        let (m0, m1) = (self.second_derivatives[i], self.second_derivatives[i + 1]);
+
+        m1 * (b * b / (2.0 * h)) - m0 * (a * a / (2.0 * h))
+            + (self.points[i + 1] - self.points[i]) / h
+            - (m1 - m0) * (h / 6.0)
+    }
+
+    pub fn second_derivative(&self, t: f32) -> SVector<f32, D> {
+        let (i, t) = self.interval(t);
+        let h = self.parameters[i + 1] - self.parameters[i];
+        let (a, b) = (self.parameters[i + 1] - t, t - self.parameters[i]);
+
+        (self.second_derivatives[i] * a + self.second_derivatives[i + 1] * b) / h
+    }
+
+    /// De Boor points of the same curve as a uniform cubic B-spline, which requires uniformly
+    /// spaced parameters. The points of a periodic spline repeat cyclically, an open spline gets
+    /// an extra point at either end.
+    pub fn deboor_points(&self) -> Vec<SVector<f32, D>> {
+        let h = self.parameters[1] - self.parameters[0];
+        assert!(
+            self.parameters
+                .windows(2)
+                .all(|pair| ((pair[1] - pair[0]) - h).abs() <= 1.0e-4 * h),
+            "De Boor points need uniformly spaced parameters"
+        );
+
+        // A uniform cubic B-spline has the value (d[i-1] + 4 d[i] + d[i+1]) / 6 and the second
+        // derivative (d[i-1] - 2 d[i] + d[i+1]) / h^2 at its knots
+        let inner = self
+            .points
+            .iter()
+            .zip(&self.second_derivatives)
+            .map(|(point, second_derivative)| point - second_derivative * (h * h / 6.0));
+
+        if self.periodic {
+            return inner.take(self.points.len() - 1).collect();
+        }
+
+        // Its first derivative at a knot is (d[i+1] - d[i-1]) / 2h
+        let mut points: Vec<_> = inner.collect();
+        let start = self.parameters[0];
+        let end = *self.parameters.last().unwrap();
+        let first = points[1] - self.derivative(start) * (2.0 * h);
+        let last = points[points.len() - 2] + self.derivative(end) * (2.0 * h);
+
+        points.insert(0, first);
+        points.push(last);
+        points
+    }
+
+    /// Index of the interval containing `t` and `t` moved into the domain
+    fn interval(&self, t: f32) -> (usize, f32) {
+        let start = self.parameters[0];
+        let end = *self.parameters.last().unwrap();
+        let t = if self.periodic {
+            start + (t - start).rem_euclid(end - start)
+        } else {
+            t.clamp(start, end)
+        };
+
+        let i = self
+            .parameters
+            .partition_point(|&parameter| parameter <= t)
+            .saturating_sub(1)
+            .min(self.parameters.len() - 2);
+
+        (i, t)
+    }
+
+    fn second_derivatives(
+        parameters: &[f32],
+        points: &[SVector<f32, D>],
+        end: EndCondition<D>,
+    ) -> Vec<SVector<f32, D>> {
+        let n = points.len() - 1;
+        let h: Vec<_> = parameters
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .collect();
+        let slope = |i: usize| (points[i + 1] - points[i]) / h[i];
+
+        if end == EndCondition::Periodic {
+            // Unknowns for the points 0..n, the point n is the point 0 again
+            let previous = |i: usize| (i + n - 1) % n;
+            let diagonal = (0..n).map(|i| 2.0 * (h[previous(i)] + h[i])).collect();
+            let lower: Vec<_> = (1..n).map(|i| h[i - 1]).collect();
+            let upper: Vec<_> = (0..n - 1).map(|i| h[i]).collect();
+            let free_term = (0..n)
+                .map(|i| (slope(i) - slope(previous(i))) * 6.0)
+                .collect();
+
+            let mut second_derivatives =
+                cyclic_equation_system(diagonal, &lower, &upper, h[n - 1], free_term);
+            second_derivatives.push(second_derivatives[0]);
+            return second_derivatives;
+        }
+
+        let mut diagonal = vec![0.0; n + 1];
+        let mut lower = vec![0.0; n];
+        let mut upper = vec![0.0; n];
+        let mut free_term = vec![SVector::zeros(); n + 1];
+
+        for i in 1..n {
+            lower[i - 1] = h[i - 1];
+            diagonal[i] = 2.0 * (h[i - 1] + h[i]);
+            upper[i] = h[i];
+            free_term[i] = (slope(i) - slope(i - 1)) * 6.0;
+        }
+
+        match end {
+            EndCondition::Clamped(start, end) => {
+                diagonal[0] = 2.0 * h[0];
+                upper[0] = h[0];
+                free_term[0] = (slope(0) - start) * 6.0;
+
+                lower[n - 1] = h[n - 1];
+                diagonal[n] = 2.0 * h[n - 1];
+                free_term[n] = (end - slope(n - 1)) * 6.0;
+            }
+            _ => {
+                diagonal[0] = 1.0;
+                diagonal[n] = 1.0;
+            }
+        }
+
+        tridiagonal::equation_system(diagonal, &lower, &upper, free_term)
+    }
+}
+
+/// Solves a tridiagonal system that also has the entry `corner` in its top right and bottom
+/// left corner, by the Sherman-Morrison formula on two tridiagonal systems
+fn cyclic_equation_system<const D: usize>(
+    mut diagonal: Vec<f32>,
+    lower_diagonal: &[f32],
+    upper_diagonal: &[f32],
+    corner: f32,
+    free_term: Vec<SVector<f32, D>>,
+) -> Vec<SVector<f32, D>> {
+    let n = diagonal.len();
+    let gamma = -diagonal[0];
+
+    diagonal[0] -= gamma;
+    diagonal[n - 1] -= corner * corner / gamma;
+
+    let mut correction = vec![0.0; n];
+    correction[0] = gamma;
+    correction[n - 1] = corner;
+
+    let solution =
+        tridiagonal::equation_system(diagonal.clone(), lower_diagonal, upper_diagonal, free_term);
+    let z = tridiagonal::equation_system(diagonal, lower_diagonal, upper_diagonal, correction);
+
+    let factor = (solution[0] + solution[n - 1] * (corner / gamma))
+        / (1.0 + z[0] + corner * z[n - 1] / gamma);
+
+    solution
+        .into_iter()
+        .zip(z)
+        .map(|(x, z)| x - factor * z)
+        .collect()
+}
@@ -0,0 +1,463 @@
+use crate::{
+    duck::Duck,
+    math::{
+        geometry::{
+            bezier::BezierBSpline,
+            curve::{BoundingBox, Curve3},
+            hermite::HermiteSpline,
+        },
+        interpolating_spline::{EndCondition, InterpolatingSpline},
+    },
+    water::Water,
+};
+use nalgebra::{Point2, Point3, Vector2};
+use rand::{distributions, rngs::StdRng, Rng};
+use std::{fmt, fs, io, path::Path, str::FromStr};
+
+/// Kind of curve the path of a duck is built as from its points
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PathCurve {
+    /// Uniform cubic B-spline with the points as de Boor points, which stays within their
+    /// convex hull
+    #[default]
+    BSpline,
+    /// Centripetal Catmull-Rom spline through the points
+    CatmullRom,
+    /// Kochanek-Bartels spline through the points
+    Tcb {
+        tension: f32,
+        continuity: f32,
+        bias: f32,
+    },
+}
+
+impl PathCurve {
+    /// Curve from `points`, of which an interpolating curve passes through all but the first
+    /// and the last. With the points within `bounds`, see `PlannerContext::bounds`, the curve
+    /// stays within them too.
+    pub fn build(&self, points: Vec<Point3<f32>>, bounds: Vector2<f32>) -> Box<dyn Curve3> {
+        let mut spline = match *self {
+            // Stays within the convex hull of its points
+            Self::BSpline => return Box::new(BezierBSpline::through_points(points)),
+            Self::CatmullRom => HermiteSpline::catmull_rom(points),
+            Self::Tcb {
+                tension,
+                continuity,
+                bias,
+            } => HermiteSpline::kochanek_bartels(points, tension, continuity, bias),
+        };
+
+        spline.confine(&BoundingBox::new(
+            Point3::new(-bounds.x, f32::NEG_INFINITY, -bounds.y),
+            Point3::new(bounds.x, f32::INFINITY, bounds.y),
+        ));
+        Box::new(spline)
+    }
+
+    /// Whether the curve passes through its points instead of only approaching them
+    pub fn interpolates(&self) -> bool {
+        *self != Self::BSpline
+    }
+}
+
+/// What a `PathPlanner` can take into account when choosing the next point of a path
+pub struct PlannerContext<'a> {
+    pub rng: &'a mut StdRng,
+    pub water: &'a Water,
+    /// Height of the points of the path
+    pub height: f32,
+    /// Largest distance of the points from the center of the pool along either axis, which
+    /// keeps them away from the walls
+    pub bounds: Vector2<f32>,
+    /// Last points of the paths of the other ducks
+    pub path_ends: &'a [Point3<f32>],
+    /// Point on the water plane under the cursor, if any
+    pub cursor: Option<Point2<f32>>,
+    /// Kind of curve the points are used for
+    pub curve: PathCurve,
+}
+
+impl<'a> PlannerContext<'a> {
+    /// Moves `point` within the `bounds`. The curves `build` makes from such points stay
+    /// within them.
+    pub fn clamp(&self, point: Point3<f32>) -> Point3<f32> {
+        Point3::new(
+            point.x.clamp(-self.bounds.x, self.bounds.x),
+            point.y,
+            point.z.clamp(-self.bounds.y, self.bounds.y),
+        )
+    }
+
+    /// The `curve` through `points`, within the `bounds`
+    pub fn build(&self, points: Vec<Point3<f32>>) -> Box<dyn Curve3> {
+        self.curve.build(points, self.bounds)
+    }
+}
+
+/// Chooses the points of the path a duck follows, which is built from four points as the
+/// `PathCurve` of the context. Whenever the duck reaches the end of its path, the first point is
+/// dropped and the next point of the planner appended.
+pub trait PathPlanner: fmt::Debug {
+    /// Four points of the path of a new duck
+    fn initial_points(&mut self, context: &mut PlannerContext) -> Vec<Point3<f32>> {
+        let mut points = Vec::new();
+        for _ in 0..4 {
+            let point = self.next_point(&points, context);
+            points.push(point);
+        }
+
+        points
+    }
+
+    /// Point to append to the path with the points `points`
+    fn next_point(&mut self, points: &[Point3<f32>], context: &mut PlannerContext) -> Point3<f32>;
+
+    fn clone_box(&self) -> Box<dyn PathPlanner>;
+}
+
+impl Clone for Box<dyn PathPlanner> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Wanders between random points, keeping clear of land and of the path ends of other ducks
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RandomWander;
+
+impl RandomWander {
+    const PATH_TRIES: usize = 100;
+    const PATH_CHECK_SAMPLES: usize = 32;
+
+    fn random_points(context: &mut PlannerContext) -> Vec<Point3<f32>> {
+        (0..4).map(|_| Self::random_point(context)).collect()
+    }
+
+    /// Samples a point above water within the bounds of the pool, gives up and returns a point
+    /// on land after `PATH_TRIES` unsuccessful attempts
+    fn random_point(context: &mut PlannerContext) -> Point3<f32> {
+        let bounds = context.bounds;
+        let x_dist = distributions::Uniform::new_inclusive(-bounds.x, bounds.x);
+        let z_dist = distributions::Uniform::new_inclusive(-bounds.y, bounds.y);
+        let mut sample = || {
+            Point3::new(
+                context.rng.sample(x_dist),
+                context.height,
+                context.rng.sample(z_dist),
+            )
+        };
+
+        let mut point = sample();
+        for _ in 1..Self::PATH_TRIES {
+            if !context.water.is_land_at(point.x, point.z) {
+                break;
+            }
+
+            point = sample();
+        }
+
+        point
+    }
+
+    /// Separation steering of the planner, ducks do not head for the same spot
+    fn keeps_apart(point: &Point3<f32>, path_ends: &[Point3<f32>]) -> bool {
+        path_ends.iter().all(|end| {
+            Vector2::new(end.x - point.x, end.z - point.z).norm() >= Duck::SEPARATION_RADIUS
+        })
+    }
+
+    /// Checks the segment ending with the last of `points`, as well as the segment that would
+    /// follow if the duck kept heading towards that point, so that the path does not run into a
+    /// dead end that no next point can get out of
+    fn extension_avoids_land(points: &[Point3<f32>], context: &PlannerContext) -> bool {
+        let mut lookahead = points[1..].to_vec();
+        lookahead.push(*points.last().unwrap());
+
+        Self::avoids_land(context.build(points.to_vec()).as_ref(), context.water)
+            && Self::avoids_land(context.build(lookahead).as_ref(), context.water)
+    }
+
+    fn avoids_land(path: &dyn Curve3, water: &Water) -> bool {
+        let domain = path.domain();
+        (0..=Self::PATH_CHECK_SAMPLES).all(|i| {
+            let t = i as f32 / Self::PATH_CHECK_SAMPLES as f32;
+            let position = path.value(domain.start + t * (domain.end - domain.start));
+            !water.is_land_at(position.x, position.z)
+        })
+    }
+}
+
+impl PathPlanner for RandomWander {
+    fn initial_points(&mut self, context: &mut PlannerContext) -> Vec<Point3<f32>> {
+        let mut points = Self::random_points(context);
+
+        for _ in 1..Self::PATH_TRIES {
+            if Self::extension_avoids_land(&points, context) {
+                break;
+            }
+
+            points = Self::random_points(context);
+        }
+
+        points
+    }
+
+    fn next_point(&mut self, points: &[Point3<f32>], context: &mut PlannerContext) -> Point3<f32> {
+        let mut window = points.iter().skip(1).copied().collect::<Vec<_>>();
+        window.push(Self::random_point(context));
+
+        for _ in 1..Self::PATH_TRIES {
+            let point = window.last().unwrap();
+            if Self::extension_avoids_land(&window, context)
+                && Self::keeps_apart(point, context.path_ends)
+            {
+                break;
+            }
+
+            *window.last_mut().unwrap() = Self::random_point(context);
+        }
+
+        *window.last().unwrap()
+    }
+
+    fn clone_box(&self) -> Box<dyn PathPlanner> {
+        Box::new(*self)
+    }
+}
+
+/// Goes round a loop of waypoints on the water plane. A B-spline path is the periodic
+/// interpolating spline through the waypoints and interpolating curves take the waypoints
+/// themselves, so the duck passes exactly through each of them either way.
+///
+/// Routes are stored as text with the `x z` coordinates of a waypoint on every line, empty lines
+/// and lines starting with `#` are ignored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WaypointLoop {
+    waypoints: Vec<Point2<f32>>,
+    /// De Boor points of the interpolating spline, one per waypoint
+    deboor_points: Vec<Point2<f32>>,
+    /// Index of the waypoint or de Boor point returned next
+    next: usize,
+}
+
+impl WaypointLoop {
+    pub const MIN_WAYPOINTS: usize = 3;
+
+    pub fn new(waypoints: Vec<Point2<f32>>) -> Self {
+        assert!(
+            waypoints.len() >= Self::MIN_WAYPOINTS,
+            "A route needs at least {} waypoints",
+            Self::MIN_WAYPOINTS
+        );
+
+        let spline = InterpolatingSpline::uniform(
+            waypoints.iter().map(|waypoint| waypoint.coords).collect(),
+            EndCondition::Periodic,
+        );
+
+        Self {
+            deboor_points: spline
+                .deboor_points()
+                .into_iter()
+                .map(Point2::from)
+                .collect(),
+            waypoints,
+            next: 0,
+        }
+    }
+
+    pub fn waypoints(&self) -> &[Point2<f32>] {
+        &self.waypoints
+    }
+
+    /// Checks that a duck following the route stays within `bounds`, see
+    /// `PlannerContext::bounds`. The de Boor points are checked, as both they and the waypoints
+    /// have to be, and the waypoints lie within the convex hull of the de Boor points.
+    pub fn check_bounds(&self, bounds: Vector2<f32>) -> Result<(), String> {
+        match self
+            .deboor_points
+            .iter()
+            .find(|point| point.x.abs() > bounds.x || point.y.abs() > bounds.y)
+        {
+            Some(point) => Err(format!(
+                "The route passes ({:.2}, {:.2}), outside of the {:.2} by {:.2} m ducks can reach",
+                point.x,
+                point.y,
+                2.0 * bounds.x,
+                2.0 * bounds.y
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Same route, continued from the waypoint at `idx`, so that several ducks can spread over
+    /// it
+    pub fn starting_at(mut self, idx: usize) -> Self {
+        self.next = idx % self.waypoints.len();
+        self
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl PathPlanner for WaypointLoop {
+    fn next_point(&mut self, _points: &[Point3<f32>], context: &mut PlannerContext) -> Point3<f32> {
+        let point = if context.curve.interpolates() {
+            self.waypoints[self.next]
+        } else {
+            self.deboor_points[self.next]
+        };
+        self.next = (self.next + 1) % self.waypoints.len();
+
+        Point3::new(point.x, context.height, point.y)
+    }
+
+    fn clone_box(&self) -> Box<dyn PathPlanner> {
+        Box::new(self.clone())
+    }
+}
+
+impl FromStr for WaypointLoop {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let waypoints = s
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(line_number, line)| {
+                let coordinates = line
+                    .split_whitespace()
+                    .map(str::parse)
+                    .collect::<Result<Vec<f32>, _>>()
+                    .map_err(|err| format!("Line {}: {}", line_number, err))?;
+
+                match coordinates[..] {
+                    [x, z] => Ok(Point2::new(x, z)),
+                    _ => Err(format!(
+                        "Line {}: expected two coordinates, found {}",
+                        line_number,
+                        coordinates.len()
+                    )),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if waypoints.len() < Self::MIN_WAYPOINTS {
+            return Err(format!(
+                "A route needs at least {} waypoints, found {}",
+                Self::MIN_WAYPOINTS,
+                waypoints.len()
+            ));
+        }
+
+        Ok(Self::new(waypoints))
+    }
+}
+
+impl fmt::Display for WaypointLoop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for waypoint in &self.waypoints {
+            writeln!(f, "{} {}", waypoint.x, waypoint.y)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Swims figure eights, a lemniscate of Gerono stretched to `half_extent`
+#[derive(Clone, Debug, PartialEq)]
+pub struct FigureEight {
+    pub center: Point2<f32>,
+    pub half_extent: Vector2<f32>,
+    /// Index of the point on the loop returned next
+    phase: usize,
+}
+
+impl FigureEight {
+    /// Points per loop
+    const SAMPLES: usize = 12;
+
+    pub fn new(center: Point2<f32>, half_extent: Vector2<f32>) -> Self {
+        Self {
+            center,
+            half_extent,
+            phase: 0,
+        }
+    }
+}
+
+impl PathPlanner for FigureEight {
+    fn next_point(&mut self, _points: &[Point3<f32>], context: &mut PlannerContext) -> Point3<f32> {
+        let angle = std::f32::consts::TAU * self.phase as f32 / Self::SAMPLES as f32;
+        self.phase = (self.phase + 1) % Self::SAMPLES;
+
+        Point3::new(
+            self.center.x + self.half_extent.x * angle.sin(),
+            context.height,
+            self.center.y + self.half_extent.y * (2.0 * angle).sin(),
+        )
+    }
+
+    fn clone_box(&self) -> Box<dyn PathPlanner> {
+        Box::new(self.clone())
+    }
+}
+
+/// Heads for the cursor, or the center of the pool while there is none, in steps of `step`
+/// meters and circles the target once it is reached. Does not look out for land.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FollowCursor {
+    pub step: f32,
+}
+
+impl FollowCursor {
+    pub fn new(step: f32) -> Self {
+        Self { step }
+    }
+}
+
+impl PathPlanner for FollowCursor {
+    fn initial_points(&mut self, context: &mut PlannerContext) -> Vec<Point3<f32>> {
+        RandomWander.initial_points(context)
+    }
+
+    fn next_point(&mut self, points: &[Point3<f32>], context: &mut PlannerContext) -> Point3<f32> {
+        let Some(&last) = points.last() else {
+            return RandomWander::random_point(context);
+        };
+
+        let position = Point2::new(last.x, last.z);
+        let target = context.cursor.unwrap_or_else(Point2::origin);
+        let offset = target - position;
+
+        let direction = if offset.norm() > self.step {
+            offset.normalize()
+        } else {
+            // Turning at every point keeps the duck circling the target
+            let heading = points
+                .len()
+                .checked_sub(2)
+                .map(|idx| position - Point2::new(points[idx].x, points[idx].z))
+                .and_then(|heading| heading.try_normalize(f32::EPSILON))
+                .unwrap_or_else(Vector2::x);
+            Vector2::new(-heading.y, heading.x)
+        };
+
+        let next = position + self.step * direction;
+        Point3::new(next.x, context.height, next.y)
+    }
+
+    fn clone_box(&self) -> Box<dyn PathPlanner> {
+        Box::new(*self)
+    }
+}
@@ -1,5 +1,6 @@
+use super::color::Color;
 use glow::HasContext;
-use nalgebra::{Point3, Vector2, Vector3};
+use nalgebra::{Matrix4, Point3, Vector2, Vector3, Vector4};
 
 #[derive(Clone, Copy, Debug)]
 #[repr(C)]
@@ -85,6 +86,59 @@ impl Vertex for SimpleVertex {
     }
 }
 
+/// Per-instance attributes of an instanced duck. The model transform takes up locations 3 to
+/// 6, one for each column.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct DuckInstance {
+    pub model_transform: Matrix4<f32>,
+    pub tint: Color,
+}
+
+impl DuckInstance {
+    pub fn new(model_transform: Matrix4<f32>, tint: Color) -> Self {
+        Self {
+            model_transform,
+            tint,
+        }
+    }
+}
+
+impl Vertex for DuckInstance {
+    fn set_vertex_attrib_pointers(gl: &glow::Context) {
+        let stride = std::mem::size_of::<DuckInstance>() as i32;
+        let column_size = std::mem::size_of::<Vector4<f32>>() as i32;
+
+        unsafe {
+            // Model transform
+            for column in 0..4 {
+                gl.vertex_attrib_pointer_f32(
+                    3 + column,
+                    4,
+                    glow::FLOAT,
+                    false,
+                    stride,
+                    column as i32 * column_size,
+                );
+                gl.enable_vertex_attrib_array(3 + column);
+                gl.vertex_attrib_divisor(3 + column, 1);
+            }
+
+            // Tint
+            gl.vertex_attrib_pointer_f32(
+                7,
+                3,
+                glow::FLOAT,
+                false,
+                stride,
+                std::mem::size_of::<Matrix4<f32>>() as i32,
+            );
+            gl.enable_vertex_attrib_array(7);
+            gl.vertex_attrib_divisor(7, 1);
+        }
+    }
+}
+
 pub trait Vertex {
     fn set_vertex_attrib_pointers(gl: &glow::Context);
 }
@@ -0,0 +1,70 @@
+use crate::water_disturbance::{Disturbance, DisturbanceKernel};
+use nalgebra::Vector2;
+use rand::Rng;
+
+/// Raindrops falling on the water as a Poisson process, so that their density does not depend
+/// on the resolution of the grid or the length of the steps
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rain {
+    /// Mean number of drops per square meter and second
+    pub intensity: f32,
+    /// Radii of the drops are distributed uniformly between these
+    pub min_radius: f32,
+    pub max_radius: f32,
+    /// Height a drop adds at its center, negative for drops denting the surface
+    pub amplitude: f32,
+}
+
+impl Rain {
+    /// Drops falling on a rectangle of `extent` centered at the origin during `delta` seconds.
+    /// Takes time proportional to the number of drops.
+    pub fn drops(&self, rng: &mut impl Rng, extent: Vector2<f32>, delta: f32) -> Vec<Disturbance> {
+        let mean = self.intensity * extent.x * extent.y * delta;
+
+        (0..Self::poisson(rng, mean))
+            .map(|_| {
+                let radius = if self.max_radius > self.min_radius {
+                    rng.gen_range(self.min_radius..self.max_radius)
+                } else {
+                    self.min_radius
+                };
+
+                Disturbance::new(
+                    (rng.gen::<f32>() - 0.5) * extent.x,
+                    (rng.gen::<f32>() - 0.5) * extent.y,
+                    radius,
+                    self.amplitude,
+                    DisturbanceKernel::RaisedCosine,
+                )
+            })
+            .collect()
+    }
+
+    /// Number of events with exponentially distributed gaps that happen in a unit interval when
+    /// `mean` of them are expected
+    fn poisson(rng: &mut impl Rng, mean: f32) -> usize {
+        let mut count = 0;
+        let mut time = 0.0;
+
+        loop {
+            // 1 - gen() lies in (0, 1], which keeps the logarithm finite
+            time -= (1.0 - rng.gen::<f32>()).ln() / mean;
+            if time > 1.0 || !time.is_finite() {
+                return count;
+            }
+
+            count += 1;
+        }
+    }
+}
+
+impl Default for Rain {
+    fn default() -> Self {
+        Self {
+            intensity: 0.05,
+            min_radius: 0.08,
+            max_radius: 0.15,
+            amplitude: -0.1,
+        }
+    }
+}
@@ -7,6 +7,9 @@ pub struct GlMesh<'gl> {
     element_buffer: u32,
     element_count: u32,
     vertex_array: u32,
+    /// Per-instance attributes, `None` if the mesh is drawn once
+    instance_buffer: Option<u32>,
+    instance_count: u32,
     gl: &'gl glow::Context,
 }
 
@@ -32,20 +35,67 @@ impl<'gl> GlMesh<'gl> {
             element_buffer,
             element_count: 3 * mesh.triangles.len() as u32,
             vertex_array,
+            instance_buffer: None,
+            instance_count: 0,
             gl,
         }
     }
+
+    /// Mesh drawn once for each of the instances set with `set_instances`, whose attributes are
+    /// described by `I`
+    pub fn instanced<V: Vertex, I: Vertex>(gl: &'gl glow::Context, mesh: &Mesh<V>) -> Self {
+        let mut gl_mesh = Self::new(gl, mesh);
+        let instance_buffer = unsafe { gl.create_buffer() }.unwrap();
+
+        opengl::with_vao(gl, gl_mesh.vertex_array, || unsafe {
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(instance_buffer));
+            I::set_vertex_attrib_pointers(gl);
+        });
+
+        gl_mesh.instance_buffer = Some(instance_buffer);
+        gl_mesh
+    }
+
+    /// Replaces the instances of a mesh created with `instanced`
+    pub fn set_instances<I: Vertex>(&mut self, instances: &[I]) {
+        let instance_buffer = self
+            .instance_buffer
+            .expect("Instances set on a mesh that is not instanced");
+
+        unsafe {
+            self.gl
+                .bind_buffer(glow::ARRAY_BUFFER, Some(instance_buffer));
+            self.gl.buffer_data_u8_slice(
+                glow::ARRAY_BUFFER,
+                utils::slice_as_raw(instances),
+                glow::DYNAMIC_DRAW,
+            );
+            self.gl.bind_buffer(glow::ARRAY_BUFFER, None);
+        }
+
+        self.instance_count = instances.len() as u32;
+    }
 }
 
 impl<'gl> GlDrawable for GlMesh<'gl> {
     fn draw(&self) {
         opengl::with_vao(self.gl, self.vertex_array, || unsafe {
-            self.gl.draw_elements(
-                glow::TRIANGLES,
-                self.element_count as i32,
-                glow::UNSIGNED_INT,
-                0,
-            );
+            if self.instance_buffer.is_some() {
+                self.gl.draw_elements_instanced(
+                    glow::TRIANGLES,
+                    self.element_count as i32,
+                    glow::UNSIGNED_INT,
+                    0,
+                    self.instance_count as i32,
+                );
+            } else {
+                self.gl.draw_elements(
+                    glow::TRIANGLES,
+                    self.element_count as i32,
+                    glow::UNSIGNED_INT,
+                    0,
+                );
+            }
         });
     }
 }
@@ -56,6 +106,10 @@ impl<'gl> Drop for GlMesh<'gl> {
             self.gl.delete_vertex_array(self.vertex_array);
             self.gl.delete_buffer(self.vertex_buffer);
             self.gl.delete_buffer(self.element_buffer);
+
+            if let Some(instance_buffer) = self.instance_buffer {
+                self.gl.delete_buffer(instance_buffer);
+            }
         }
     }
 }
@@ -92,6 +92,27 @@ impl<'gl> GlProgram<'gl> {
         }
     }
 
+    pub fn uniform_2_f32(&self, name: &str, x: f32, y: f32) {
+        unsafe {
+            let location = self.gl.get_uniform_location(self.handle, name).expect(name);
+            self.gl.uniform_2_f32(Some(&location), x, y);
+        }
+    }
+
+    pub fn uniform_2_i32(&self, name: &str, x: i32, y: i32) {
+        unsafe {
+            let location = self.gl.get_uniform_location(self.handle, name).expect(name);
+            self.gl.uniform_2_i32(Some(&location), x, y);
+        }
+    }
+
+    pub fn uniform_4_i32(&self, name: &str, x: i32, y: i32, z: i32, w: i32) {
+        unsafe {
+            let location = self.gl.get_uniform_location(self.handle, name).expect(name);
+            self.gl.uniform_4_i32(Some(&location), x, y, z, w);
+        }
+    }
+
     pub fn uniform_3_f32(&self, name: &str, x: f32, y: f32, z: f32) {
         unsafe {
             let location = self.gl.get_uniform_location(self.handle, name).expect(name);
@@ -1,28 +1,110 @@
-use crate::render::texture::Texture;
+use crate::{render::texture::Texture, utils};
 use glow::HasContext;
 
-fn texture_format(texture: &Texture) -> u32 {
-    match texture.image {
-        image::DynamicImage::ImageRgb8(_) => glow::RGB,
-        image::DynamicImage::ImageRgba8(_) => glow::RGBA,
-        _ => panic!("Unsupported texture format"),
+/// Layout of the texels of a texture on the GPU
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureFormat {
+    Rgb8,
+    Rgba8,
+    /// Half precision floats, uploaded from 32 bit floats
+    Rgba16F,
+    Rgba32F,
+    /// Single 32 bit float channel, e.g. for heights
+    R32F,
+}
+
+impl TextureFormat {
+    /// Format with the precision of `texture`
+    pub fn of(texture: &Texture) -> Self {
+        match texture.image {
+            image::DynamicImage::ImageRgb8(_) => Self::Rgb8,
+            image::DynamicImage::ImageRgba8(_) => Self::Rgba8,
+            image::DynamicImage::ImageRgba32F(_) => Self::Rgba32F,
+            _ => panic!("Unsupported texture format"),
+        }
+    }
+
+    fn internal_format(self) -> u32 {
+        match self {
+            Self::Rgb8 => glow::RGB8,
+            Self::Rgba8 => glow::RGBA8,
+            Self::Rgba16F => glow::RGBA16F,
+            Self::Rgba32F => glow::RGBA32F,
+            Self::R32F => glow::R32F,
+        }
+    }
+
+    /// Format of the data uploaded to a texture of this format
+    fn pixel_format(self) -> u32 {
+        match self {
+            Self::Rgb8 => glow::RGB,
+            Self::Rgba8 | Self::Rgba16F | Self::Rgba32F => glow::RGBA,
+            Self::R32F => glow::RED,
+        }
+    }
+
+    fn pixel_type(self) -> u32 {
+        match self {
+            Self::Rgb8 | Self::Rgba8 => glow::UNSIGNED_BYTE,
+            Self::Rgba16F | Self::Rgba32F | Self::R32F => glow::FLOAT,
+        }
+    }
+
+    /// Whether textures of `texture`'s format can be uploaded to a texture of this format
+    fn accepts(self, texture: &Texture) -> bool {
+        match Self::of(texture) {
+            Self::Rgba32F => matches!(self, Self::Rgba16F | Self::Rgba32F),
+            format => format == self,
+        }
     }
 }
 
 pub struct GlTexture<'gl> {
     gl: &'gl glow::Context,
     handle: u32,
+    format: TextureFormat,
 }
 
 impl<'gl> GlTexture<'gl> {
+    /// Creates a texture with the precision of `texture`
     pub fn new(gl: &'gl glow::Context, texture: &Texture) -> Self {
+        Self::with_format(gl, texture, TextureFormat::of(texture))
+    }
+
+    /// Creates a texture stored in `format`, which may have a lower precision than `texture`
+    pub fn with_format(gl: &'gl glow::Context, texture: &Texture, format: TextureFormat) -> Self {
         let handle = Self::create_and_bind(gl);
 
-        let gl_texture = Self { gl, handle };
+        let gl_texture = Self { gl, handle, format };
         gl_texture.load(texture);
         gl_texture
     }
 
+    /// Creates a single channel float texture from `width * height` values stored row by row
+    pub fn from_r32f(gl: &'gl glow::Context, width: u32, height: u32, data: &[f32]) -> Self {
+        let handle = Self::create_and_bind(gl);
+
+        let gl_texture = Self {
+            gl,
+            handle,
+            format: TextureFormat::R32F,
+        };
+        gl_texture.load_r32f(width, height, data);
+        gl_texture
+    }
+
+    /// Creates a texture with undefined contents, to be filled with `load_from_buffer`
+    pub fn empty(gl: &'gl glow::Context, width: u32, height: u32, format: TextureFormat) -> Self {
+        let handle = Self::create_and_bind(gl);
+        let gl_texture = Self { gl, handle, format };
+        gl_texture.upload(width, height, None);
+        gl_texture
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
     fn create_and_bind(gl: &glow::Context) -> u32 {
         unsafe {
             let texture = gl
@@ -52,21 +134,67 @@ impl<'gl> GlTexture<'gl> {
     }
 
     pub fn load(&self, texture: &Texture) {
-        let format = texture_format(texture);
+        assert!(
+            self.format.accepts(texture),
+            "Cannot load the texture into a {:?} texture",
+            self.format
+        );
+
+        self.upload(
+            texture.image.width(),
+            texture.image.height(),
+            Some(texture.image.as_bytes()),
+        );
+    }
+
+    pub fn load_r32f(&self, width: u32, height: u32, data: &[f32]) {
+        assert_eq!(self.format, TextureFormat::R32F);
+        assert_eq!(data.len(), (width * height) as usize);
+
+        self.upload(width, height, Some(utils::slice_as_raw(data)));
+    }
 
+    /// Replaces the contents with the tightly packed texels at the start of a GPU buffer, in the
+    /// layout `load` expects of the CPU data
+    pub fn load_from_buffer(&self, buffer: u32, width: u32, height: u32) {
         unsafe {
+            self.gl.bind_buffer(glow::PIXEL_UNPACK_BUFFER, Some(buffer));
             self.gl.bind_texture(glow::TEXTURE_2D, Some(self.handle));
+            self.gl.tex_sub_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                0,
+                0,
+                width as i32,
+                height as i32,
+                self.format.pixel_format(),
+                self.format.pixel_type(),
+                glow::PixelUnpackData::BufferOffset(0),
+            );
+            self.gl.bind_buffer(glow::PIXEL_UNPACK_BUFFER, None);
+            self.gl.generate_mipmap(glow::TEXTURE_2D);
+        }
+    }
+
+    fn upload(&self, width: u32, height: u32, data: Option<&[u8]>) {
+        unsafe {
+            self.gl.bind_texture(glow::TEXTURE_2D, Some(self.handle));
+
+            // Rows of single channel and RGB textures are not necessarily 4 byte aligned
+            self.gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
             self.gl.tex_image_2d(
                 glow::TEXTURE_2D,
                 0,
-                format as i32,
-                texture.image.width() as i32,
-                texture.image.height() as i32,
+                self.format.internal_format() as i32,
+                width as i32,
+                height as i32,
                 0,
-                format,
-                glow::UNSIGNED_BYTE,
-                Some(texture.image.as_bytes()),
+                self.format.pixel_format(),
+                self.format.pixel_type(),
+                data,
             );
+            self.gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 4);
+
             self.gl.generate_mipmap(glow::TEXTURE_2D);
         }
     }
@@ -139,10 +267,10 @@ impl<'gl> GlCubeTexture<'gl> {
     }
 
     pub fn load(&self, textures: &[Texture; 6]) {
-        let format = texture_format(&textures[0]);
+        let format = TextureFormat::of(&textures[0]);
 
         for texture in textures.iter().skip(1) {
-            assert_eq!(texture_format(texture), format);
+            assert_eq!(TextureFormat::of(texture), format);
         }
 
         self.bind();
@@ -152,12 +280,12 @@ impl<'gl> GlCubeTexture<'gl> {
                 self.gl.tex_image_2d(
                     glow::TEXTURE_CUBE_MAP_POSITIVE_X + idx as u32,
                     0,
-                    format as i32,
+                    format.internal_format() as i32,
                     texture.image.width() as i32,
                     texture.image.height() as i32,
                     0,
-                    format,
-                    glow::UNSIGNED_BYTE,
+                    format.pixel_format(),
+                    format.pixel_type(),
                     Some(texture.image.as_bytes()),
                 );
             }
@@ -4,7 +4,7 @@ use nalgebra::{Point3, Vector2, Vector3};
 #[derive(Debug)]
 pub struct ParseError;
 
-pub struct Triangle([u32; 3]);
+pub struct Triangle(pub [u32; 3]);
 
 pub struct Mesh<V: Vertex> {
     pub vertices: Vec<V>,
@@ -19,6 +19,14 @@ impl Mesh<DuckVertex> {
         Self::parse_model(&string).expect("Error parsing model file")
     }
 
+    /// Radius of the smallest sphere around the origin of the model that contains all vertices
+    pub fn bounding_radius(&self) -> f32 {
+        self.vertices
+            .iter()
+            .map(|vertex| vertex.position.coords.norm())
+            .fold(0.0, f32::max)
+    }
+
     pub fn parse_model(string: &str) -> Result<Self, ParseError> {
         let mut lines = string.lines();
 
@@ -89,6 +97,41 @@ impl Mesh<SimpleVertex> {
         }
     }
 
+    /// Grid of `columns` by `rows` vertices evenly spanning the unit square on the xz plane,
+    /// facing up
+    pub fn grid(columns: u32, rows: u32) -> Self {
+        assert!(columns >= 2 && rows >= 2);
+
+        let vertices = (0..rows)
+            .flat_map(|row| {
+                (0..columns).map(move |column| {
+                    SimpleVertex::new(
+                        column as f32 / (columns - 1) as f32,
+                        0.0,
+                        row as f32 / (rows - 1) as f32,
+                    )
+                })
+            })
+            .collect();
+
+        let triangles = (0..rows - 1)
+            .flat_map(|row| (0..columns - 1).map(move |column| row * columns + column))
+            .flat_map(|i| {
+                let (right, down) = (i + 1, i + columns);
+                let diagonal = down + 1;
+                [
+                    Triangle([i, down, diagonal]),
+                    Triangle([i, diagonal, right]),
+                ]
+            })
+            .collect();
+
+        Self {
+            vertices,
+            triangles,
+        }
+    }
+
     pub fn inner_cube() -> Self {
         let vertices = vec![
             SimpleVertex::new(0.0, 0.0, 0.0),
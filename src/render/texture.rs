@@ -1,4 +1,4 @@
-use image::{DynamicImage, RgbaImage};
+use image::{DynamicImage, Rgba32FImage, RgbaImage};
 
 #[derive(Debug)]
 pub struct Texture {
@@ -21,4 +21,11 @@ impl Texture {
 
         Self { image }
     }
+
+    pub fn new_rgba32f(width: u32, height: u32) -> Self {
+        let image_buffer = Rgba32FImage::new(width, height);
+        let image = DynamicImage::ImageRgba32F(image_buffer);
+
+        Self { image }
+    }
 }
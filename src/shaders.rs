@@ -4,7 +4,7 @@ use std::path::Path;
 const SHADERS_PATH: &str = "shaders/";
 const SHADERS_EXTENSION: &str = "glsl";
 
-pub fn create_shader_manager(gl: &glow::Context) -> ShaderManager {
+pub fn create_shader_manager(gl: &glow::Context) -> ShaderManager<'_> {
     let cube_vert = shader(gl, "cube_vert", glow::VERTEX_SHADER);
     let duck_vert = shader(gl, "duck_vert", glow::VERTEX_SHADER);
     let water_vert = shader(gl, "water_vert", glow::VERTEX_SHADER);
@@ -29,6 +29,12 @@ pub fn create_shader_manager(gl: &glow::Context) -> ShaderManager {
     ])
 }
 
+/// Program with a single compute shader
+pub fn create_compute_program<'gl>(gl: &'gl glow::Context, name: &str) -> GlProgram<'gl> {
+    let compute = shader(gl, name, glow::COMPUTE_SHADER);
+    GlProgram::with_shaders(gl, &[&compute])
+}
+
 fn shader<'gl>(gl: &'gl glow::Context, name: &str, kind: u32) -> Shader<'gl> {
     let mut path = Path::new(SHADERS_PATH).join(name);
     path.set_extension(SHADERS_EXTENSION);
@@ -0,0 +1,426 @@
+use crate::{
+    duck::Duck,
+    fixed_timestep::FixedTimestep,
+    path_planner::{PathCurve, PathPlanner, PlannerContext, RandomWander},
+    primitives::color::Color,
+    rain::Rain,
+    water::Water,
+    water_disturbance::Disturbance,
+};
+use nalgebra::{Point2, Point3, Vector2};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{ops::Range, time::Duration};
+
+/// Change made to the water by a `Simulation` step, see `Simulation::record_water_events`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WaterEvent {
+    /// `Water::stamp` with the given disturbance
+    Stamp(Disturbance),
+    /// `Water::update`
+    Update,
+}
+
+/// State of the duck scene that does not depend on any graphics context.
+/// Rendering code only reads from it, so it can be stepped on machines without a display.
+pub struct Simulation {
+    water: Water,
+    water_timestep: FixedTimestep,
+    pub interpolate_water: bool,
+    /// Heights interpolated for display, kept between frames to reuse the allocation
+    displayed_heights: Vec<f32>,
+    /// Changes to the water since the last `take_water_events`, `None` if the water is
+    /// integrated on the CPU
+    water_events: Option<Vec<WaterEvent>>,
+
+    ducks: Vec<Duck>,
+    /// Whether the ducks float by their buoyancy instead of sticking to the surface, off by
+    /// default
+    pub duck_buoyancy: bool,
+    /// Radius of a sphere around a duck that contains its model, the ducks keep this far from
+    /// the walls of the pool. `Duck::MODEL_RADIUS` by default.
+    duck_radius: f32,
+    pub rain: Rain,
+    /// Point on the water plane under the cursor, followed by `FollowCursor` planners
+    pub cursor: Option<Point2<f32>>,
+
+    rng: StdRng,
+}
+
+impl Simulation {
+    pub const ENVIRONMENT_SCALE: f32 = 10.0;
+    pub const WATER_SAMPLES: usize = 256;
+    pub const WATER_EXTENT: Vector2<f32> =
+        Vector2::new(Self::ENVIRONMENT_SCALE, Self::ENVIRONMENT_SCALE);
+
+    pub const DEFAULT_DUCK_COUNT: usize = 3;
+    const DUCK_Y: f32 = -2.7;
+    /// Tints given to the ducks in turn
+    const DUCK_TINTS: [Color; 4] = [
+        Color {
+            r: 1.0,
+            g: 1.0,
+            b: 1.0,
+        },
+        Color {
+            r: 1.0,
+            g: 0.6,
+            b: 0.6,
+        },
+        Color {
+            r: 0.6,
+            g: 0.8,
+            b: 1.0,
+        },
+        Color {
+            r: 0.7,
+            g: 1.0,
+            b: 0.6,
+        },
+    ];
+
+    /// In metres per second. The water used to span 2 units at 0.2 units per second, so this
+    /// crosses the pool in the same time.
+    const DEFAULT_WAVE_SPEED: f32 = 1.0;
+    const DEFAULT_WATER_RATE: f32 = 240.0;
+    const MAX_WATER_STEPS: u32 = 10;
+
+    /// Creates a new scene. Two simulations created with the same `seed` and stepped with the
+    /// same deltas evolve identically.
+    pub fn new(seed: u64) -> Self {
+        Self::with_water(seed, Self::default_water())
+    }
+
+    /// Water surface used by `new`
+    pub fn default_water() -> Water {
+        Water::new(
+            Self::WATER_SAMPLES,
+            Self::WATER_SAMPLES,
+            Self::WATER_EXTENT,
+            Self::DEFAULT_WAVE_SPEED,
+            1.0 / Self::DEFAULT_WATER_RATE,
+        )
+    }
+
+    /// Creates a new scene on the given water surface. The timestep of `water` is replaced with
+    /// the one of the simulation.
+    pub fn with_water(seed: u64, mut water: Water) -> Self {
+        let rng = StdRng::seed_from_u64(seed);
+        let water_timestep = FixedTimestep::new(Self::DEFAULT_WATER_RATE, Self::MAX_WATER_STEPS);
+        water.set_timestep(water_timestep.timestep());
+
+        let mut simulation = Self {
+            water,
+            water_timestep,
+            interpolate_water: true,
+            displayed_heights: Vec::new(),
+            water_events: None,
+
+            ducks: Vec::new(),
+            duck_buoyancy: false,
+            duck_radius: Duck::MODEL_RADIUS,
+            rain: Rain::default(),
+            cursor: None,
+
+            rng,
+        };
+
+        for _ in 0..Self::DEFAULT_DUCK_COUNT {
+            simulation.add_duck();
+        }
+
+        simulation
+    }
+
+    pub fn water(&self) -> &Water {
+        &self.water
+    }
+
+    pub fn water_mut(&mut self) -> &mut Water {
+        &mut self.water
+    }
+
+    /// Water and the heights it should be displayed with this frame, interpolated between the
+    /// last two solver steps if `interpolate_water` is set.
+    pub fn displayed_water(&mut self) -> (&Water, &[f32]) {
+        if self.interpolate_water {
+            let alpha = self.water_alpha();
+            self.water
+                .interpolate_heights(alpha, &mut self.displayed_heights);
+            (&self.water, &self.displayed_heights)
+        } else {
+            (&self.water, self.water.heights())
+        }
+    }
+
+    /// Interpolation factor between the previous (0) and the current (1) water step for this
+    /// frame, always 1 unless `interpolate_water` is set
+    pub fn water_alpha(&self) -> f32 {
+        if self.interpolate_water {
+            self.water_timestep.alpha()
+        } else {
+            1.0
+        }
+    }
+
+    /// Stops integrating the water on the CPU and records the changes made to it instead, so
+    /// that they can be replayed by another solver. The parameters of `water` remain in use,
+    /// while its heights only change when that solver writes them back with
+    /// `Water::heights_mut`, so until then the ducks float on the last heights written. Solvers
+    /// need only write back the `duck_water_rows`, unless `displayed_water` is read.
+    pub fn record_water_events(&mut self) {
+        self.water_events.get_or_insert_with(Vec::new);
+    }
+
+    /// Rows of the water grid the ducks read heights from, sorted and apart. A duck reads within
+    /// `Duck::MODEL_RADIUS` of its center, which covers its hull and how far it moves in a frame.
+    pub fn duck_water_rows(&self) -> Vec<Range<usize>> {
+        let depth = self.water.depth();
+        let mut rows: Vec<Range<usize>> = self
+            .ducks
+            .iter()
+            .map(|duck| {
+                let z = duck.position().y;
+                let grid = |z: f32| self.water.grid_position(0.0, z).y;
+                // Surface normals read the rows next to the ones interpolated between
+                let start = (grid(z - Duck::MODEL_RADIUS).floor() - 1.0).clamp(0.0, depth as f32);
+                let end = (grid(z + Duck::MODEL_RADIUS).ceil() + 2.0).clamp(0.0, depth as f32);
+                start as usize..end as usize
+            })
+            .collect();
+        rows.sort_by_key(|rows| rows.start);
+
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        for range in rows {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+
+    /// Changes made to the water since the last call, empty unless `record_water_events` was
+    /// called
+    pub fn take_water_events(&mut self) -> Vec<WaterEvent> {
+        self.water_events
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Number of water solver steps per second of simulation
+    pub fn water_rate(&self) -> f32 {
+        self.water_timestep.rate()
+    }
+
+    pub fn set_water_rate(&mut self, rate: f32) {
+        self.water_timestep.set_rate(rate);
+        self.water.set_timestep(self.water_timestep.timestep());
+    }
+
+    pub fn ducks(&self) -> &[Duck] {
+        &self.ducks
+    }
+
+    pub fn ducks_iter_mut(&mut self) -> std::slice::IterMut<'_, Duck> {
+        self.ducks.iter_mut()
+    }
+
+    /// Adds `duck` on its own path, which is kept within the bounds of the pool from now on
+    pub fn push_duck(&mut self, mut duck: Duck) {
+        duck.set_bounds(self.duck_bounds());
+        self.ducks.push(duck);
+    }
+
+    /// Removes the duck at `idx`, the following ducks move down by one
+    pub fn remove_duck(&mut self, idx: usize) -> Duck {
+        self.ducks.remove(idx)
+    }
+
+    pub fn duck_radius(&self) -> f32 {
+        self.duck_radius
+    }
+
+    /// Sets the radius the ducks keep from the walls, usually the bounding radius of the duck
+    /// mesh times `Duck::SCALE`, and plans new paths for the ducks that keep to it
+    pub fn set_duck_radius(&mut self, radius: f32) {
+        self.duck_radius = radius;
+
+        let bounds = self.duck_bounds();
+        let mut path_ends = self.path_ends();
+        let mut ducks = std::mem::take(&mut self.ducks);
+        for idx in 0..ducks.len() {
+            let others: Vec<_> = path_ends
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != idx)
+                .map(|(_, end)| *end)
+                .collect();
+            let mut context = self.planner_context(&others, ducks[idx].curve());
+            let points = Self::initial_points(ducks[idx].planner.as_mut(), &mut context);
+            ducks[idx].set_bounds(bounds);
+            ducks[idx].reset_path(points);
+            // The ducks after this one plan against its new end
+            path_ends[idx] = *ducks[idx].points().last().unwrap();
+        }
+
+        self.ducks = ducks;
+    }
+
+    /// Adds a duck wandering on a random path
+    pub fn add_duck(&mut self) {
+        self.add_duck_with(Box::new(RandomWander));
+    }
+
+    /// Adds a duck on a path planned by `planner`
+    pub fn add_duck_with(&mut self, mut planner: Box<dyn PathPlanner>) {
+        let path_ends = self.path_ends();
+        let mut context = self.planner_context(&path_ends, PathCurve::default());
+        let points = Self::initial_points(planner.as_mut(), &mut context);
+
+        let tint = Self::DUCK_TINTS[self.ducks.len() % Self::DUCK_TINTS.len()];
+        let mut duck = Duck::with_curve(points, PathCurve::default(), tint);
+        duck.planner = planner;
+        self.push_duck(duck);
+    }
+
+    pub fn step(&mut self, delta: Duration) {
+        for _ in 0..self.water_timestep.advance(delta) {
+            self.update_water();
+        }
+
+        self.update_ducks(delta.as_secs_f32());
+    }
+
+    fn update_water(&mut self) {
+        let drops = self
+            .rain
+            .drops(&mut self.rng, self.water.extent(), self.water.timestep());
+        for drop in drops {
+            self.stamp_water(drop);
+        }
+
+        match &mut self.water_events {
+            Some(events) => events.push(WaterEvent::Update),
+            None => self
+                .water
+                .update()
+                .expect("Wave speed too high for the water solver"),
+        }
+    }
+
+    /// Adds a disturbance to the water, or records it while recording water events
+    pub fn stamp_water(&mut self, disturbance: Disturbance) {
+        match &mut self.water_events {
+            Some(events) => events.push(WaterEvent::Stamp(disturbance)),
+            None => self.water.stamp(&disturbance),
+        }
+    }
+
+    fn update_ducks(&mut self, delta: f32) {
+        // Updated as the ducks move, so that ducks on top of each other split up
+        let mut positions: Vec<_> = self.ducks.iter().map(Duck::position).collect();
+        let mut path_ends = self.path_ends();
+
+        // The ducks are taken out, so that they can be updated while stamping the water
+        let mut ducks = std::mem::take(&mut self.ducks);
+
+        for (idx, duck) in ducks.iter_mut().enumerate() {
+            let others = |points: &[Point3<f32>]| {
+                let mut points = points.to_vec();
+                points.remove(idx);
+                points
+            };
+            let other_positions = positions
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != idx)
+                .map(|(_, position)| *position);
+            duck.steer_apart(delta, other_positions, self.duck_bounds());
+
+            let progress = duck.advance(delta);
+            let end = duck.path().domain().end;
+
+            // The wake follows the old path up to its end before moving on to the new one
+            if progress.end >= end {
+                self.stamp_all(duck.wake(progress.start..end));
+                let points = self.extended_points(duck, &others(&path_ends));
+                duck.extend_path(points);
+                // The ducks after this one plan against its new end
+                path_ends[idx] = *duck.points().last().unwrap();
+                let start = duck.path().domain().start;
+                self.stamp_all(duck.wake(start..duck.progress()));
+            } else {
+                self.stamp_all(duck.wake(progress));
+            }
+
+            let disturbances = duck.place(delta, &self.water, self.duck_buoyancy);
+            self.stamp_all(disturbances);
+            positions[idx] = duck.position();
+        }
+
+        self.ducks = ducks;
+    }
+
+    fn stamp_all(&mut self, disturbances: Vec<Disturbance>) {
+        for disturbance in disturbances {
+            self.stamp_water(disturbance);
+        }
+    }
+
+    /// Last points of the paths of all ducks
+    fn path_ends(&self) -> Vec<Point3<f32>> {
+        self.ducks
+            .iter()
+            .map(|duck| *duck.points().last().unwrap())
+            .collect()
+    }
+
+    /// Largest distance of a duck from the center of the pool along either axis
+    pub fn duck_bounds(&self) -> Vector2<f32> {
+        (0.5 * self.water.extent()).map(|half| (half - self.duck_radius).max(0.0))
+    }
+
+    fn planner_context<'a>(
+        &'a mut self,
+        path_ends: &'a [Point3<f32>],
+        curve: PathCurve,
+    ) -> PlannerContext<'a> {
+        let bounds = self.duck_bounds();
+        PlannerContext {
+            rng: &mut self.rng,
+            water: &self.water,
+            height: Self::DUCK_Y,
+            bounds,
+            path_ends,
+            cursor: self.cursor,
+            curve,
+        }
+    }
+
+    /// Initial points of `planner`, moved into the bounds of the pool
+    fn initial_points(
+        planner: &mut dyn PathPlanner,
+        context: &mut PlannerContext,
+    ) -> Vec<Point3<f32>> {
+        planner
+            .initial_points(context)
+            .into_iter()
+            .map(|point| context.clamp(point))
+            .collect()
+    }
+
+    /// Drops the first point of the path of `duck` and appends the next point of its planner,
+    /// which sees the `path_ends` of the other ducks. The point is moved into the bounds of the
+    /// pool, so the path does not run into the walls whatever the planner.
+    fn extended_points(&mut self, duck: &mut Duck, path_ends: &[Point3<f32>]) -> Vec<Point3<f32>> {
+        let mut points = duck.points().to_vec();
+        let mut context = self.planner_context(path_ends, duck.curve());
+        let next = duck.planner.next_point(&points, &mut context);
+        let next = context.clamp(next);
+
+        points.remove(0);
+        points.push(next);
+        points
+    }
+}
@@ -1,12 +1,45 @@
 pub fn slice_as_raw<T>(slice: &[T]) -> &[u8] {
     unsafe {
-        core::slice::from_raw_parts(
-            slice.as_ptr() as *const u8,
-            slice.len() * core::mem::size_of::<T>(),
+        core::slice::from_raw_parts(slice.as_ptr() as *const u8, core::mem::size_of_val(slice))
+    }
+}
+
+/// Every byte pattern is a valid `f32`, so the bytes can be written freely
+pub fn slice_as_raw_mut(slice: &mut [f32]) -> &mut [u8] {
+    unsafe {
+        core::slice::from_raw_parts_mut(
+            slice.as_mut_ptr() as *mut u8,
+            core::mem::size_of_val(slice),
         )
     }
 }
 
+/// Maps a normal component from [-1, 1] to [0, 255]
 pub fn normal_f32_to_u8(val: f32) -> u8 {
-    (val * 127.0) as u8 + 127
+    ((val.clamp(-1.0, 1.0) + 1.0) * 127.5).round() as u8
+}
+
+/// Calls `action` with the index and contents of every `row_len` long row of `data`. Rows are
+/// processed in parallel if `parallel` is set and the `parallel` feature is enabled.
+pub fn for_each_row<T, F>(data: &mut [T], row_len: usize, parallel: bool, action: F)
+where
+    T: Send,
+    F: Fn(usize, &mut [T]) + Sync,
+{
+    #[cfg(feature = "parallel")]
+    if parallel {
+        use rayon::prelude::*;
+
+        data.par_chunks_mut(row_len)
+            .enumerate()
+            .for_each(|(idx, row)| action(idx, row));
+        return;
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    let _ = parallel;
+
+    for (idx, row) in data.chunks_mut(row_len).enumerate() {
+        action(idx, row);
+    }
 }
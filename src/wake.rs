@@ -0,0 +1,107 @@
+use crate::{
+    math::geometry::curve::Curve3,
+    water_disturbance::{Disturbance, DisturbanceKernel},
+};
+use nalgebra::Vector2;
+use std::ops::Range;
+
+/// Emits the wake of a hull moving along a path. Every `spacing` meters of track the bow pushes
+/// water up and the stern leaves a trough of the same volume, so the wake is continuous however
+/// far the hull moves in a frame and its height grows with the speed of the hull. The solver is
+/// not dispersive, so instead of at the Kelvin angle the arms of the V open at the Mach angle
+/// `asin(wave_speed / speed)` of a hull faster than the waves.
+#[derive(Clone, Debug)]
+pub struct WakeEmitter {
+    /// Distance between emissions along the track
+    pub spacing: f32,
+    pub radius: f32,
+    /// Volume of water moved from the stern to the bow per meter of track
+    pub volume_per_meter: f32,
+    /// Distance from the center of the hull to its bow and stern
+    pub half_length: f32,
+    /// Distance travelled since the last emission
+    travelled: f32,
+}
+
+impl WakeEmitter {
+    /// Longest step along the track between two speed evaluations, relative to the spacing
+    const MAX_STEP: f32 = 0.5;
+
+    pub fn new(spacing: f32, radius: f32, volume_per_meter: f32, half_length: f32) -> Self {
+        assert!(spacing > 0.0);
+
+        Self {
+            spacing,
+            radius,
+            volume_per_meter,
+            half_length,
+            travelled: 0.0,
+        }
+    }
+
+    /// Moves the hull along `path` over the parameter range `progress` and returns the
+    /// disturbances emitted on the way, multiplied by `strength`
+    pub fn emit(
+        &mut self,
+        path: &dyn Curve3,
+        progress: Range<f32>,
+        strength: f32,
+    ) -> Vec<Disturbance> {
+        let mut disturbances = Vec::new();
+        let mut t = progress.start;
+
+        while t < progress.end {
+            let speed = path.derivative(t).xz().norm();
+            if speed <= f32::EPSILON {
+                break;
+            }
+
+            // Short steps keep the emissions evenly spaced where the speed along the path
+            // changes
+            let remaining = self.spacing - self.travelled;
+            let emits = remaining <= Self::MAX_STEP * self.spacing;
+            let distance = if emits {
+                remaining
+            } else {
+                Self::MAX_STEP * self.spacing
+            };
+            let dt = distance / speed;
+
+            if t + dt > progress.end {
+                self.travelled += (progress.end - t) * speed;
+                break;
+            }
+
+            t += dt;
+
+            if emits {
+                self.travelled = 0.0;
+                disturbances.extend(self.emit_at(path, t, strength));
+            } else {
+                self.travelled += distance;
+            }
+        }
+
+        disturbances
+    }
+
+    fn emit_at(&self, path: &dyn Curve3, t: f32, strength: f32) -> [Disturbance; 2] {
+        let position = path.value(t);
+        let center = Vector2::new(position.x, position.z);
+        let forward = path.derivative(t).xz().normalize();
+        let volume = strength * self.volume_per_meter * self.spacing;
+
+        let at = |offset: f32, volume: f32| {
+            let point = center + forward * offset;
+            Disturbance::with_volume(
+                point.x,
+                point.y,
+                self.radius,
+                volume,
+                DisturbanceKernel::RaisedCosine,
+            )
+        };
+
+        [at(self.half_length, volume), at(-self.half_length, -volume)]
+    }
+}
@@ -1,151 +1,726 @@
-use crate::{render::texture::Texture, utils};
-use image::{GenericImage, Rgba};
-use nalgebra::Vector3;
+use crate::{
+    bathymetry::Bathymetry,
+    land_mask::LandMask,
+    render::texture::Texture,
+    utils,
+    water_boundary::{self, Boundaries, Boundary},
+    water_disturbance::{Disturbance, DisturbancePatch},
+};
+use nalgebra::{Point2, Vector2, Vector3};
+use std::ops::{Add, Mul, Range};
 
+/// Returned when the wave equation cannot be integrated stably with the current parameters
+#[derive(Clone, Copy, Debug)]
+pub struct InstabilityError {
+    pub courant_number: f32,
+}
+
+#[derive(Clone, Debug)]
 pub struct Water {
     width: usize,
+    depth: usize,
+    extent: Vector2<f32>,
 
     pub wave_speed: f32,
+    /// Whether to update rows of the grid in parallel, has no effect unless the `parallel`
+    /// feature is enabled
+    pub parallel: bool,
+    /// Amplitude is multiplied by `exp(-damping_rate)` every second of simulation
+    pub damping_rate: f32,
+    timestep: f32,
+    boundaries: Boundaries,
+    land: LandMask,
+    bathymetry: Bathymetry,
 
     heights: Vec<f32>,
     heights_swap: Vec<f32>,
 }
 
 impl Water {
-    const PATCH_SIDE_LEN: f32 = 2.0;
+    pub const DEFAULT_DAMPING_RATE: f32 = 3.08;
+    /// Damping rate at the outer edge of an absorbing sponge layer, relative to the inverse of
+    /// the time a wave takes to cross the layer
+    pub(crate) const SPONGE_STRENGTH: f32 = 3.0;
+
+    /// Stability limit of the explicit scheme, see `courant_number`
+    pub const MAX_COURANT_NUMBER: f32 = 1.0;
+    pub const MAX_SUBSTEPS: usize = 256;
 
-    pub fn new(width: usize, wave_speed: f32) -> Self {
-        let heights = vec![0.0; width * width];
+    /// Creates a grid of `width` samples along the x axis and `depth` samples along the z axis,
+    /// covering `extent` world units centered at the origin. `wave_speed` is given in world
+    /// units per second and `timestep` is the length of a single `update` in seconds.
+    pub fn new(
+        width: usize,
+        depth: usize,
+        extent: Vector2<f32>,
+        wave_speed: f32,
+        timestep: f32,
+    ) -> Self {
+        assert!(width >= 2 && depth >= 2);
+
+        let heights = vec![0.0; width * depth];
         Self {
             width,
+            depth,
+            extent,
 
             wave_speed,
+            parallel: true,
+            damping_rate: Self::DEFAULT_DAMPING_RATE,
+            timestep,
+            boundaries: Boundaries::default(),
+            land: LandMask::empty(width, depth),
+            bathymetry: Bathymetry::uniform(width, depth),
 
             heights_swap: heights.clone(),
             heights,
         }
     }
 
-    fn height_index(&self, x: isize, y: isize) -> usize {
-        let x = x.clamp(0, self.width as isize - 1) as usize;
-        let y = y.clamp(0, self.width as isize - 1) as usize;
-        self.width * y + x
+    pub fn width(&self) -> usize {
+        self.width
     }
 
-    pub fn height(&self, x: isize, y: isize) -> f32 {
-        let idx = self.height_index(x, y);
-        self.heights[idx]
+    pub fn depth(&self) -> usize {
+        self.depth
     }
 
-    fn height_mut(&mut self, x: isize, y: isize) -> &mut f32 {
-        let idx = self.height_index(x, y);
-        &mut self.heights[idx]
+    pub fn extent(&self) -> Vector2<f32> {
+        self.extent
     }
 
-    pub fn height_swap(&self, x: isize, y: isize) -> f32 {
-        let idx = self.height_index(x, y);
-        self.heights_swap[idx]
+    /// Maps a world-space position on the water plane to fractional grid coordinates
+    pub fn grid_position(&self, x: f32, z: f32) -> Point2<f32> {
+        Point2::new(
+            (x / self.extent.x + 0.5) * (self.width - 1) as f32,
+            (z / self.extent.y + 0.5) * (self.depth - 1) as f32,
+        )
     }
 
-    fn height_swap_mut(&mut self, x: isize, y: isize) -> &mut f32 {
-        let idx = self.height_index(x, y);
-        &mut self.heights_swap[idx]
+    /// Maps fractional grid coordinates to a world-space position on the water plane, the
+    /// inverse of `grid_position`
+    pub fn world_position(&self, x: f32, y: f32) -> Point2<f32> {
+        Point2::new(
+            (x / (self.width - 1) as f32 - 0.5) * self.extent.x,
+            (y / (self.depth - 1) as f32 - 0.5) * self.extent.y,
+        )
     }
 
-    pub fn normal(&self, x: isize, y: isize) -> Vector3<f32> {
-        let x_tangent = Vector3::new(
-            Self::PATCH_SIDE_LEN / self.width as f32,
-            0.5 * (self.height(x - 1, y) - self.height(x + 1, y)),
-            0.0,
+    pub fn timestep(&self) -> f32 {
+        self.timestep
+    }
+
+    pub fn set_timestep(&mut self, timestep: f32) {
+        self.timestep = timestep;
+    }
+
+    pub fn boundaries(&self) -> Boundaries {
+        self.boundaries
+    }
+
+    pub fn set_boundaries(&mut self, boundaries: Boundaries) {
+        assert!(
+            boundaries.is_valid(),
+            "Periodic boundaries have to be set on opposite edges"
         );
+        self.boundaries = boundaries;
+    }
+
+    pub fn land(&self) -> &LandMask {
+        &self.land
+    }
+
+    /// Replaces the land mask. Waves reflect off land samples and their heights stay at zero.
+    pub fn set_land_mask(&mut self, land: LandMask) {
+        assert_eq!((land.width(), land.depth()), (self.width, self.depth));
+
+        for idx in 0..self.heights.len() {
+            if land.is_land_index(idx) {
+                self.heights[idx] = 0.0;
+                self.heights_swap[idx] = 0.0;
+            }
+        }
 
-        let z_tangent = Vector3::new(
-            0.0,
-            0.5 * (self.height(x, y - 1) - self.height(x, y + 1)),
-            Self::PATCH_SIDE_LEN / self.width as f32,
+        self.land = land;
+    }
+
+    pub fn bathymetry(&self) -> &Bathymetry {
+        &self.bathymetry
+    }
+
+    /// Replaces the depth profile, which scales the wave speed at every sample
+    pub fn set_bathymetry(&mut self, bathymetry: Bathymetry) {
+        assert_eq!(
+            (bathymetry.width(), bathymetry.depth()),
+            (self.width, self.depth)
         );
+        self.bathymetry = bathymetry;
+    }
+
+    /// Local wave speed at the given sample
+    pub fn wave_speed_at(&self, x: usize, y: usize) -> f32 {
+        self.wave_speed * self.bathymetry.speed_scale(x, y)
+    }
+
+    /// Whether the sample at the given coordinates is land after applying the boundary
+    /// conditions
+    pub fn is_land(&self, x: isize, y: isize) -> bool {
+        self.height_index(x, y)
+            .is_some_and(|idx| self.land.is_land_index(idx))
+    }
+
+    /// Whether a world-space position on the water plane is on land, positions outside of the
+    /// grid count as land
+    pub fn is_land_at(&self, x: f32, z: f32) -> bool {
+        let position = self.grid_position(x, z);
+        let (x, y) = (position.x.round(), position.y.round());
+
+        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.depth as f32 {
+            return true;
+        }
+
+        self.land.is_land(x as usize, y as usize)
+    }
+
+    /// Index of the sample at the given coordinates after applying the boundary conditions,
+    /// `None` if it lies beyond a fixed edge
+    fn height_index(&self, x: isize, y: isize) -> Option<usize> {
+        let b = &self.boundaries;
+        let x = water_boundary::resolve(x, self.width, b.min_x, b.max_x)?;
+        let y = water_boundary::resolve(y, self.depth, b.min_z, b.max_z)?;
+        Some(self.width * y + x)
+    }
+
+    pub fn heights(&self) -> &[f32] {
+        &self.heights
+    }
+
+    /// Heights of the step before the current one
+    pub fn previous_heights(&self) -> &[f32] {
+        &self.heights_swap
+    }
+
+    /// Current and previous heights, for solvers that integrate the water elsewhere and copy
+    /// their results back
+    pub fn heights_mut(&mut self) -> (&mut [f32], &mut [f32]) {
+        (&mut self.heights, &mut self.heights_swap)
+    }
+
+    pub fn height(&self, x: isize, y: isize) -> f32 {
+        self.height_in(&self.heights, x, y)
+    }
+
+    fn height_in(&self, heights: &[f32], x: isize, y: isize) -> f32 {
+        self.height_index(x, y).map_or(0.0, |idx| heights[idx])
+    }
+
+    pub fn height_swap(&self, x: isize, y: isize) -> f32 {
+        self.height_index(x, y)
+            .map_or(0.0, |idx| self.heights_swap[idx])
+    }
+
+    pub fn normal(&self, x: isize, y: isize) -> Vector3<f32> {
+        self.normal_in(&self.heights, x, y)
+    }
+
+    /// Normal of the surface given by `heights`, laid out like the heights of the water
+    fn normal_in(&self, heights: &[f32], x: isize, y: isize) -> Vector3<f32> {
+        let step = self.derivative_step();
+        let height = |x, y| self.height_in(heights, x, y);
+
+        let x_tangent = Vector3::new(step.x, 0.5 * (height(x + 1, y) - height(x - 1, y)), 0.0);
+
+        let z_tangent = Vector3::new(0.0, 0.5 * (height(x, y + 1) - height(x, y - 1)), step.y);
 
         Vector3::cross(&z_tangent, &x_tangent).normalize()
     }
 
-    fn normal_rgba(&self, x: isize, y: isize) -> Rgba<u8> {
-        let n = self.normal(x, y);
-        Rgba([
-            utils::normal_f32_to_u8(n.x),
-            utils::normal_f32_to_u8(n.y),
-            utils::normal_f32_to_u8(n.z),
-            utils::normal_f32_to_u8(0.0),
-        ])
+    /// Height of the surface at a world-space position on the water plane, bilinearly
+    /// interpolated between the samples
+    pub fn surface_height(&self, x: f32, z: f32) -> f32 {
+        self.bilinear(x, z, |x, y| self.height(x, y))
+    }
+
+    /// Normal of the surface at a world-space position on the water plane, bilinearly
+    /// interpolated between the samples
+    pub fn surface_normal(&self, x: f32, z: f32) -> Vector3<f32> {
+        self.bilinear(x, z, |x, y| self.normal(x, y)).normalize()
+    }
+
+    /// Interpolates `sample` at a world-space position, positions outside of the grid are
+    /// clamped to its edges
+    fn bilinear<T>(&self, x: f32, z: f32, sample: impl Fn(isize, isize) -> T) -> T
+    where
+        T: Add<Output = T> + Mul<f32, Output = T>,
+    {
+        let position = self.grid_position(x, z);
+        let x = position.x.clamp(0.0, (self.width - 1) as f32);
+        let y = position.y.clamp(0.0, (self.depth - 1) as f32);
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as isize, y0 as isize);
+
+        let top = sample(x0, y0) * (1.0 - tx) + sample(x0 + 1, y0) * tx;
+        let bottom = sample(x0, y0 + 1) * (1.0 - tx) + sample(x0 + 1, y0 + 1) * tx;
+        top * (1.0 - ty) + bottom * ty
     }
 
     pub fn disturb(&mut self, x: isize, y: isize, height: f32) {
-        *self.height_mut(x, y) = height;
+        if let Some(idx) = self.disturbance_index(x, y) {
+            self.heights[idx] = height;
+        }
     }
 
-    fn derivative_step(&self) -> f32 {
-        2.0 / (self.width as f32 - 1.0)
+    /// Index into `heights` of the sample `disturb` sets at the given coordinates, `None` if the
+    /// disturbance is ignored
+    fn disturbance_index(&self, x: isize, y: isize) -> Option<usize> {
+        self.height_index(x, y)
+            .filter(|idx| !self.land.is_land_index(*idx))
     }
 
-    fn damping_coeff(&self, x: isize, y: isize) -> f32 {
-        let border_distance = f32::max(
-            (x as f32 - Self::PATCH_SIDE_LEN).abs(),
-            (y as f32 - Self::PATCH_SIDE_LEN).abs(),
-        );
+    /// Adds a smooth disturbance to the heights. Land and the parts beyond the edges of the
+    /// grid are left out.
+    pub fn stamp(&mut self, disturbance: &Disturbance) {
+        self.add_patch(&self.disturbance_patch(disturbance));
+    }
 
-        0.95 * f32::min(1.0, 5.0 * border_distance)
+    /// Adds the heights of `patch` to the water
+    pub fn add_patch(&mut self, patch: &DisturbancePatch) {
+        for (y, row) in patch.rows() {
+            let heights = &mut self.heights[y * self.width..][patch.x.clone()];
+            for (height, added) in heights.iter_mut().zip(row) {
+                *height += added;
+            }
+        }
     }
 
-    fn delta(&self) -> f32 {
-        1.0 / self.width as f32
+    /// Heights `stamp` adds to the samples around the disturbance
+    pub fn disturbance_patch(&self, disturbance: &Disturbance) -> DisturbancePatch {
+        let center = self.grid_position(disturbance.x, disturbance.z);
+        let step = self.derivative_step();
+        let radius = Vector2::new(disturbance.radius / step.x, disturbance.radius / step.y);
+
+        let range = |center: f32, radius: f32, len: usize| {
+            let start = (center - radius).ceil().clamp(0.0, len as f32) as usize;
+            let end = ((center + radius).floor() + 1.0).clamp(0.0, len as f32) as usize;
+            start..end.max(start)
+        };
+        let x = range(center.x, radius.x, self.width);
+        let y = range(center.y, radius.y, self.depth);
+
+        let heights = y
+            .clone()
+            .flat_map(|y| x.clone().map(move |x| (x, y)))
+            .map(|(x, y)| {
+                if self.land.is_land(x, y) {
+                    return 0.0;
+                }
+
+                let offset = Vector2::new(
+                    (x as f32 - center.x) * step.x,
+                    (y as f32 - center.y) * step.y,
+                );
+                let r = offset.norm() / disturbance.radius;
+                disturbance.amplitude * disturbance.kernel.weight(r)
+            })
+            .collect();
+
+        DisturbancePatch { x, y, heights }
+    }
+
+    /// Distance between neighboring samples along the x and z axes
+    pub fn derivative_step(&self) -> Vector2<f32> {
+        Vector2::new(
+            self.extent.x / (self.width as f32 - 1.0),
+            self.extent.y / (self.depth as f32 - 1.0),
+        )
     }
 
-    fn a_coeff(&self) -> f32 {
+    fn damping_coeff(&self, x: usize, y: usize, timestep: f32) -> f32 {
+        let b = &self.boundaries;
+        let step = self.derivative_step();
+
+        // Scaling by the time it takes a wave to cross the layer keeps its reflectivity
+        // independent of the wave speed and grid resolution
+        let sponge = f32::max(
+            water_boundary::sponge(x, self.width, b.min_x, b.max_x) / step.x,
+            water_boundary::sponge(y, self.depth, b.min_z, b.max_z) / step.y,
+        ) * self.wave_speed_at(x, y)
+            * Self::SPONGE_STRENGTH;
+
+        (-(self.damping_rate + sponge) * timestep).exp()
+    }
+
+    /// Coefficients of the neighbors along the x and z axes for the base wave speed
+    fn a_coeff(&self, timestep: f32) -> Vector2<f32> {
         let derivative_step = self.derivative_step();
-        let delta = self.delta();
-        self.wave_speed * self.wave_speed * delta * delta
-            / (derivative_step * derivative_step)
+        let c = self.wave_speed * self.wave_speed * timestep * timestep;
+        Vector2::new(
+            c / (derivative_step.x * derivative_step.x),
+            c / (derivative_step.y * derivative_step.y),
+        )
     }
 
-    fn b_coeff(a_coeff: f32) -> f32 {
-        2.0 - 4.0 * a_coeff
+    fn b_coeff(a_coeff: Vector2<f32>) -> f32 {
+        2.0 - 2.0 * a_coeff.x - 2.0 * a_coeff.y
     }
 
-    fn neighbor_sum(&self, x: isize, y: isize) -> f32 {
-        self.height(x, y - 1)
-            + self.height(x, y + 1)
-            + self.height(x - 1, y)
-            + self.height(x + 1, y)
+    /// Height of a neighbor of a water sample, land reflects waves like a wall
+    fn neighbor_height(&self, x: isize, y: isize, nx: isize, ny: isize) -> f32 {
+        if self.is_land(nx, ny) {
+            self.height(x, y)
+        } else {
+            self.height(nx, ny)
+        }
     }
 
-    pub fn update(&mut self) {
+    fn neighbor_sum(&self, x: isize, y: isize) -> Vector2<f32> {
+        Vector2::new(
+            self.neighbor_height(x, y, x - 1, y) + self.neighbor_height(x, y, x + 1, y),
+            self.neighbor_height(x, y, x, y - 1) + self.neighbor_height(x, y, x, y + 1),
+        )
+    }
+
+    /// Courant number `c * dt * sqrt(1 / hx^2 + 1 / hz^2)` of a single `update` without
+    /// substepping, where `c` is the highest local wave speed and `hx` and `hz` are the grid
+    /// spacings
+    pub fn courant_number(&self) -> f32 {
+        let step = self.derivative_step();
+        let max_speed = self.wave_speed * self.bathymetry.max_speed_scale();
+        max_speed * self.timestep * (1.0 / (step.x * step.x) + 1.0 / (step.y * step.y)).sqrt()
+    }
+
+    /// Number of substeps `update` divides the timestep into to keep the scheme stable
+    pub fn substeps(&self) -> Result<usize, InstabilityError> {
+        let courant_number = self.courant_number();
+        let substeps = (courant_number / Self::MAX_COURANT_NUMBER).ceil().max(1.0);
+
+        if substeps <= Self::MAX_SUBSTEPS as f32 {
+            Ok(substeps as usize)
+        } else {
+            Err(InstabilityError { courant_number })
+        }
+    }
+
+    /// Advances the water by one timestep, subdividing it if the Courant number is above the
+    /// stability limit. Leaves the water untouched and returns an error if that would take more
+    /// than `MAX_SUBSTEPS` substeps.
+    pub fn update(&mut self) -> Result<(), InstabilityError> {
+        self.advance(Self::integrate)
+    }
+
+    /// Same as `update`, but integrates every sample through the boundary-aware accessors. Kept
+    /// as the reference the optimized kernel is tested and benchmarked against.
+    #[doc(hidden)]
+    pub fn update_reference(&mut self) -> Result<(), InstabilityError> {
+        self.advance(Self::integrate_reference)
+    }
+
+    fn advance(&mut self, integrate: fn(&mut Self, f32)) -> Result<(), InstabilityError> {
+        let substeps = self.substeps()?;
+        let timestep = self.timestep / substeps as f32;
+
+        if substeps == 1 {
+            integrate(self, timestep);
+            return Ok(());
+        }
+
+        // The previous heights encode the velocity over one full timestep, so they have to be
+        // rescaled to the length of a substep and back
+        self.scale_velocity(1.0 / substeps as f32);
+        for _ in 0..substeps {
+            integrate(self, timestep);
+        }
+        self.scale_velocity(substeps as f32);
+
+        Ok(())
+    }
+
+    fn scale_velocity(&mut self, scale: f32) {
+        for (previous, height) in self.heights_swap.iter_mut().zip(&self.heights) {
+            *previous = height - scale * (height - *previous);
+        }
+    }
+
+    fn integrate(&mut self, timestep: f32) {
+        let step = self.derivative_step();
+        let b = self.boundaries;
+        let sponge_x: Vec<_> = (0..self.width)
+            .map(|x| water_boundary::sponge(x, self.width, b.min_x, b.max_x) / step.x)
+            .collect();
+
+        // Sponge layers only ever cover the edges, so the undamped columns are contiguous
+        let start = sponge_x
+            .iter()
+            .position(|s| *s == 0.0)
+            .unwrap_or(self.width);
+        let start = start.clamp(1, self.width - 1);
+        let end = sponge_x
+            .iter()
+            .rposition(|s| *s == 0.0)
+            .map_or(0, |x| x + 1);
+        let end = end.clamp(start, self.width - 1);
+
+        let kernel = Kernel {
+            base_a: self.a_coeff(timestep),
+            timestep,
+            damping: (-self.damping_rate * timestep).exp(),
+            calm_x: start..end,
+            sponge_x,
+            sponge_z: (0..self.depth)
+                .map(|y| water_boundary::sponge(y, self.depth, b.min_z, b.max_z) / step.y)
+                .collect(),
+        };
+
+        self.integrate_with(|water, y, row| water.integrate_row(y, row, &kernel));
+    }
+
+    fn integrate_reference(&mut self, timestep: f32) {
         // In theory, this should be constant
-        let a = self.a_coeff();
+        let base_a = self.a_coeff(timestep);
+
+        self.integrate_with(|water, y, row| {
+            for (x, height) in row.iter_mut().enumerate() {
+                *height = water.integrate_sample(x, y, *height, base_a, timestep);
+            }
+        });
+    }
+
+    fn integrate_with<F>(&mut self, integrate_row: F)
+    where
+        F: Fn(&Self, usize, &mut [f32]) + Sync,
+    {
+        // Every sample only depends on its own previous height, so the previous heights can be
+        // overwritten in place
+        let mut next = std::mem::take(&mut self.heights_swap);
+        utils::for_each_row(&mut next, self.width, self.parallel, |y, row| {
+            integrate_row(self, y, row)
+        });
+        self.heights_swap = next;
+
+        std::mem::swap(&mut self.heights, &mut self.heights_swap);
+    }
+
+    /// New height of a single sample given its `previous` height, resolving neighbors through
+    /// the boundary conditions
+    fn integrate_sample(
+        &self,
+        x: usize,
+        y: usize,
+        previous: f32,
+        base_a: Vector2<f32>,
+        timestep: f32,
+    ) -> f32 {
+        let idx = self.width * y + x;
+        if self.land.is_land_index(idx) {
+            return 0.0;
+        }
+
+        let speed_scale = self.bathymetry.speed_scale_index(idx);
+        let a = base_a * speed_scale * speed_scale;
         let b = Self::b_coeff(a);
+        let d = self.damping_coeff(x, y, timestep);
+        let n = self.neighbor_sum(x as isize, y as isize);
 
-        for x in 0..(self.width as isize) {
-            for y in 0..(self.width as isize) {
-                let d = self.damping_coeff(x, y);
-                let n = self.neighbor_sum(x, y);
+        d * (a.dot(&n) + b * self.heights[idx] - previous)
+    }
 
-                *self.height_swap_mut(x, y) =
-                    d * (a * n + b * self.height(x, y) - self.height_swap(x, y));
+    /// Integrates the edge samples of a row one by one and the interior with `integrate_interior`
+    fn integrate_row(&self, y: usize, row: &mut [f32], kernel: &Kernel) {
+        let width = self.width;
+        if y == 0 || y == self.depth - 1 {
+            for (x, height) in row.iter_mut().enumerate() {
+                *height = self.integrate_sample(x, y, *height, kernel.base_a, kernel.timestep);
             }
+            return;
         }
 
-        std::mem::swap(&mut self.heights, &mut self.heights_swap);
+        for x in [0, width - 1] {
+            row[x] = self.integrate_sample(x, y, row[x], kernel.base_a, kernel.timestep);
+        }
+
+        let rows = StencilRows {
+            up: &self.heights[width * (y - 1)..width * y],
+            center: &self.heights[width * y..width * (y + 1)],
+            down: &self.heights[width * (y + 1)..width * (y + 2)],
+            land_up: self.land.row(y - 1),
+            land_center: self.land.row(y),
+            land_down: self.land.row(y + 1),
+            speed_scales: self.bathymetry.row(y),
+        };
+
+        // Samples outside of all sponge layers share the same damping, which spares an `exp`
+        // per sample and lets the loop vectorize
+        let sponge_z = kernel.sponge_z[y];
+        let calm = if sponge_z == 0.0 {
+            kernel.calm_x.clone()
+        } else {
+            1..1
+        };
+
+        let sponge_damping = |x: usize, speed_scale: f32| {
+            let sponge = f32::max(kernel.sponge_x[x], sponge_z)
+                * (self.wave_speed * speed_scale)
+                * Self::SPONGE_STRENGTH;
+            (-(self.damping_rate + sponge) * kernel.timestep).exp()
+        };
+
+        rows.integrate_interior(row, 1..calm.start, kernel.base_a, sponge_damping);
+        rows.integrate_interior(row, calm.clone(), kernel.base_a, |_, _| kernel.damping);
+        rows.integrate_interior(row, calm.end..width - 1, kernel.base_a, sponge_damping);
     }
 
-    pub fn normal_texture(&self) -> Texture {
-        let mut texture = Texture::new_rgba(self.width as u32, self.width as u32);
+    /// Discrete energy of the wave per unit density, which is conserved by the scheme in the
+    /// absence of damping and absorbing edges. Kinetic energy is weighted by the inverse of the
+    /// squared local speed scale, which makes it conserved over varying depth as well.
+    pub fn energy(&self) -> f32 {
+        let step = self.derivative_step();
+        let c2 = self.wave_speed * self.wave_speed;
+
+        let mut kinetic = 0.0;
+        let mut potential = 0.0;
+
+        let gradient_product = |x0: isize, y0: isize, x1: isize, y1: isize| {
+            if self.is_land(x0, y0) || self.is_land(x1, y1) {
+                return 0.0;
+            }
+
+            (self.height(x1, y1) - self.height(x0, y0))
+                * (self.height_swap(x1, y1) - self.height_swap(x0, y0))
+        };
 
         for x in 0..(self.width as isize) {
-            for y in 0..(self.width as isize) {
-                texture
-                    .image
-                    .put_pixel(x as u32, y as u32, self.normal_rgba(x, y));
+            for y in 0..(self.depth as isize) {
+                if !self.is_land(x, y) {
+                    let speed_scale = self.bathymetry.speed_scale(x as usize, y as usize);
+                    let velocity = (self.height(x, y) - self.height_swap(x, y)) / self.timestep;
+                    kinetic += velocity * velocity / (speed_scale * speed_scale);
+                }
+
+                potential += gradient_product(x, y, x + 1, y) / (step.x * step.x);
+                potential += gradient_product(x, y, x, y + 1) / (step.y * step.y);
+
+                // Interfaces with the edges that are not already counted from the other side
+                if x == 0 && self.boundaries.min_x != Boundary::Periodic {
+                    potential += gradient_product(x - 1, y, x, y) / (step.x * step.x);
+                }
+
+                if y == 0 && self.boundaries.min_z != Boundary::Periodic {
+                    potential += gradient_product(x, y - 1, x, y) / (step.y * step.y);
+                }
             }
         }
 
+        0.5 * step.x * step.y * (kinetic + c2 * potential)
+    }
+
+    /// Writes the heights linearly interpolated between the previous (`alpha == 0`) and the
+    /// current (`alpha == 1`) step into `heights`, reusing its allocation.
+    pub fn interpolate_heights(&self, alpha: f32, heights: &mut Vec<f32>) {
+        heights.clear();
+        heights.extend(
+            self.heights
+                .iter()
+                .zip(&self.heights_swap)
+                .map(|(height, previous)| previous + alpha * (height - previous)),
+        );
+    }
+
+    /// Full precision normals in the RGB channels
+    pub fn normal_texture(&self) -> Texture {
+        self.normal_texture_of(&self.heights)
+    }
+
+    /// Normals of the surface given by `heights` instead of the current step, for example the
+    /// ones from `interpolate_heights`
+    pub fn normal_texture_of(&self, heights: &[f32]) -> Texture {
+        assert_eq!(heights.len(), self.heights.len());
+
+        let mut texture = Texture::new_rgba32f(self.width as u32, self.depth as u32);
+        let pixels = texture.image.as_mut_rgba32f().unwrap();
+
+        utils::for_each_row(pixels, 4 * self.width, self.parallel, |y, row| {
+            for (x, pixel) in row.chunks_mut(4).enumerate() {
+                let n = self.normal_in(heights, x as isize, y as isize);
+                pixel.copy_from_slice(&[n.x, n.y, n.z, 0.0]);
+            }
+        });
+
         texture
     }
 }
+
+/// Per-step constants of the optimized kernel
+struct Kernel {
+    base_a: Vector2<f32>,
+    timestep: f32,
+    /// Damping of samples outside of all sponge layers
+    damping: f32,
+    /// Interior columns outside of the sponge layers along the x axis
+    calm_x: Range<usize>,
+    sponge_x: Vec<f32>,
+    sponge_z: Vec<f32>,
+}
+
+/// Rows of the grid around an interior row
+struct StencilRows<'a> {
+    up: &'a [f32],
+    center: &'a [f32],
+    down: &'a [f32],
+    land_up: &'a [bool],
+    land_center: &'a [bool],
+    land_down: &'a [bool],
+    speed_scales: &'a [f32],
+}
+
+impl StencilRows<'_> {
+    /// Integrates the samples of `row` in `range`, which must not contain the first or last
+    /// column. `damping` maps a column and its speed scale to the damping coefficient.
+    fn integrate_interior<D>(
+        &self,
+        row: &mut [f32],
+        range: Range<usize>,
+        base_a: Vector2<f32>,
+        damping: D,
+    ) where
+        D: Fn(usize, f32) -> f32,
+    {
+        if range.is_empty() {
+            return;
+        }
+
+        // Cutting every slice to the same length lets the compiler drop the bounds checks
+        let len = range.len();
+        let row = &mut row[range.clone()][..len];
+        let left = &self.center[range.start - 1..][..len];
+        let center = &self.center[range.start..][..len];
+        let right = &self.center[range.start + 1..][..len];
+        let up = &self.up[range.start..][..len];
+        let down = &self.down[range.start..][..len];
+        let land_left = &self.land_center[range.start - 1..][..len];
+        let land_center = &self.land_center[range.start..][..len];
+        let land_right = &self.land_center[range.start + 1..][..len];
+        let land_up = &self.land_up[range.start..][..len];
+        let land_down = &self.land_down[range.start..][..len];
+        let speed_scales = &self.speed_scales[range.start..][..len];
+
+        for i in 0..len {
+            let height = center[i];
+            // Land neighbors reflect waves like a wall
+            let neighbor = |land: bool, neighbor: f32| if land { height } else { neighbor };
+
+            let n_x = neighbor(land_left[i], left[i]) + neighbor(land_right[i], right[i]);
+            let n_z = neighbor(land_up[i], up[i]) + neighbor(land_down[i], down[i]);
+
+            let speed_scale = speed_scales[i];
+            let a_x = base_a.x * speed_scale * speed_scale;
+            let a_z = base_a.y * speed_scale * speed_scale;
+            let b = 2.0 - 2.0 * a_x - 2.0 * a_z;
+            let d = damping(range.start + i, speed_scale);
+
+            let next = d * (a_x * n_x + a_z * n_z + b * height - row[i]);
+            row[i] = if land_center[i] { 0.0 } else { next };
+        }
+    }
+}
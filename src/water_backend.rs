@@ -0,0 +1,53 @@
+use crate::{gpu_water::GpuWater, simulation::Simulation, water_texture::WaterTexture};
+use std::str::FromStr;
+
+/// Provides the water surface of a `Simulation` to the renderer
+pub trait WaterBackend {
+    /// Brings the surface up to date after `simulation` was stepped
+    fn update(&mut self, simulation: &mut Simulation);
+
+    /// Binds the texture with the surface normals in its RGB channels
+    fn bind_normal_texture(&self);
+
+    /// Binds the single channel texture with the surface heights
+    fn bind_height_texture(&self);
+}
+
+/// Where the water equation is integrated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaterBackendKind {
+    /// `Water` integrates the heights on the CPU, normals are uploaded every frame
+    Cpu,
+    /// Heights are integrated and normals computed by compute shaders. The rows the ducks float
+    /// on are read back into the simulation's `Water` once per frame, so the ducks follow the
+    /// waves a frame late.
+    Gpu,
+}
+
+impl WaterBackendKind {
+    pub fn create<'gl>(
+        self,
+        gl: &'gl glow::Context,
+        simulation: &mut Simulation,
+    ) -> Box<dyn WaterBackend + 'gl> {
+        match self {
+            Self::Cpu => Box::new(WaterTexture::new(gl, simulation.water())),
+            Self::Gpu => {
+                simulation.record_water_events();
+                Box::new(GpuWater::new(gl, simulation.water()))
+            }
+        }
+    }
+}
+
+impl FromStr for WaterBackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cpu" => Ok(Self::Cpu),
+            "gpu" => Ok(Self::Gpu),
+            _ => Err(format!("Unknown water backend {}, expected cpu or gpu", s)),
+        }
+    }
+}
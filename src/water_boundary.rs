@@ -0,0 +1,78 @@
+/// Condition applied to the water at one edge of the grid
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Boundary {
+    /// Zero slope across the edge (Neumann), waves bounce back with the same sign
+    Reflective,
+    /// Zero height just beyond the edge (Dirichlet), waves bounce back inverted
+    Fixed,
+    /// The grid wraps around to the opposite edge, which has to be periodic as well
+    Periodic,
+    /// Reflective edge with a sponge layer of `width` cells that damps waves entering it
+    Absorbing { width: usize },
+}
+
+/// Boundary conditions of all four edges of the water grid
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Boundaries {
+    pub min_x: Boundary,
+    pub max_x: Boundary,
+    pub min_z: Boundary,
+    pub max_z: Boundary,
+}
+
+impl Boundaries {
+    pub fn uniform(boundary: Boundary) -> Self {
+        Self {
+            min_x: boundary,
+            max_x: boundary,
+            min_z: boundary,
+            max_z: boundary,
+        }
+    }
+
+    /// Checks that periodic edges come in opposite pairs
+    pub fn is_valid(&self) -> bool {
+        (self.min_x == Boundary::Periodic) == (self.max_x == Boundary::Periodic)
+            && (self.min_z == Boundary::Periodic) == (self.max_z == Boundary::Periodic)
+    }
+}
+
+impl Default for Boundaries {
+    fn default() -> Self {
+        Self::uniform(Boundary::Reflective)
+    }
+}
+
+/// Resolves a possibly out of range coordinate along an axis of `len` samples into a sample
+/// index. Returns `None` if the coordinate refers to a fixed zero height beyond the edge.
+pub(crate) fn resolve(coord: isize, len: usize, min: Boundary, max: Boundary) -> Option<usize> {
+    let boundary = if coord < 0 {
+        min
+    } else if coord >= len as isize {
+        max
+    } else {
+        return Some(coord as usize);
+    };
+
+    match boundary {
+        Boundary::Fixed => None,
+        Boundary::Periodic => Some(coord.rem_euclid(len as isize) as usize),
+        Boundary::Reflective | Boundary::Absorbing { .. } => {
+            Some(coord.clamp(0, len as isize - 1) as usize)
+        }
+    }
+}
+
+/// Strength of the sponge layer at `coord` along an axis of `len` samples divided by the width of
+/// the layer in samples. Grows quadratically from 0 at the inner side of the layer.
+pub(crate) fn sponge(coord: usize, len: usize, min: Boundary, max: Boundary) -> f32 {
+    let layer = |boundary: Boundary, distance: usize| match boundary {
+        Boundary::Absorbing { width } if distance < width => {
+            let t = (width - distance) as f32 / width as f32;
+            t * t / width as f32
+        }
+        _ => 0.0,
+    };
+
+    f32::max(layer(min, coord), layer(max, len - 1 - coord))
+}
@@ -0,0 +1,146 @@
+use std::ops::Range;
+
+/// Profile of the bump a disturbance stamps onto the water
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DisturbanceKernel {
+    /// Gaussian truncated at three standard deviations and shifted to reach zero there
+    Gaussian,
+    /// Quarter of a cosine period, with a kink at the radius
+    Cosine,
+    /// Half of a cosine period lifted to be non-negative, smooth at the radius
+    RaisedCosine,
+}
+
+impl DisturbanceKernel {
+    /// Exponent of the Gaussian at the radius
+    const GAUSSIAN_EXPONENT: f32 = 4.5;
+
+    /// Weight at the distance `r` from the center relative to the radius, 1 at the center and
+    /// 0 from the radius on
+    pub fn weight(self, r: f32) -> f32 {
+        if r >= 1.0 {
+            return 0.0;
+        }
+
+        match self {
+            Self::Gaussian => {
+                let edge = (-Self::GAUSSIAN_EXPONENT).exp();
+                ((-Self::GAUSSIAN_EXPONENT * r * r).exp() - edge) / (1.0 - edge)
+            }
+            Self::Cosine => (std::f32::consts::FRAC_PI_2 * r).cos(),
+            Self::RaisedCosine => 0.5 * (1.0 + (std::f32::consts::PI * r).cos()),
+        }
+    }
+
+    /// Volume under the kernel with unit amplitude and the given radius
+    pub fn volume(self, radius: f32) -> f32 {
+        use std::f32::consts::PI;
+
+        let unit = match self {
+            Self::Gaussian => {
+                let edge = (-Self::GAUSSIAN_EXPONENT).exp();
+                PI * ((1.0 - edge) / Self::GAUSSIAN_EXPONENT - edge) / (1.0 - edge)
+            }
+            Self::Cosine => 4.0 - 8.0 / PI,
+            Self::RaisedCosine => PI * (0.5 - 2.0 / (PI * PI)),
+        };
+
+        unit * radius * radius
+    }
+}
+
+/// Smooth bump, or dent for a negative amplitude, added to the water around a world-space
+/// position on the water plane
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Disturbance {
+    pub x: f32,
+    pub z: f32,
+    pub radius: f32,
+    /// Height added at the center
+    pub amplitude: f32,
+    pub kernel: DisturbanceKernel,
+}
+
+impl Disturbance {
+    pub fn new(x: f32, z: f32, radius: f32, amplitude: f32, kernel: DisturbanceKernel) -> Self {
+        Self {
+            x,
+            z,
+            radius,
+            amplitude,
+            kernel,
+        }
+    }
+
+    /// Disturbance that adds `volume` of water, or removes it if negative
+    pub fn with_volume(
+        x: f32,
+        z: f32,
+        radius: f32,
+        volume: f32,
+        kernel: DisturbanceKernel,
+    ) -> Self {
+        Self::new(x, z, radius, volume / kernel.volume(radius), kernel)
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.amplitude * self.kernel.volume(self.radius)
+    }
+}
+
+/// Heights a disturbance adds to a rectangle of the water grid, stored row by row
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DisturbancePatch {
+    pub x: Range<usize>,
+    pub y: Range<usize>,
+    pub heights: Vec<f32>,
+}
+
+impl DisturbancePatch {
+    pub fn is_empty(&self) -> bool {
+        self.heights.is_empty()
+    }
+
+    /// Grid rows covered by the patch, with the heights added to them starting at `x.start`
+    pub fn rows(&self) -> impl Iterator<Item = (usize, &[f32])> {
+        self.y
+            .clone()
+            .zip(self.heights.chunks_exact(self.x.len().max(1)))
+    }
+
+    /// Adds the heights of `other`, growing the patch to the rectangle that covers both
+    pub fn add(&mut self, other: &DisturbancePatch) {
+        if other.is_empty() {
+            return;
+        }
+        if self.is_empty() {
+            self.clone_from(other);
+            return;
+        }
+
+        let x = self.x.start.min(other.x.start)..self.x.end.max(other.x.end);
+        let y = self.y.start.min(other.y.start)..self.y.end.max(other.y.end);
+        if (&x, &y) != (&self.x, &self.y) {
+            let mut grown = DisturbancePatch {
+                heights: vec![0.0; x.len() * y.len()],
+                x,
+                y,
+            };
+            grown.add_within(self);
+            *self = grown;
+        }
+
+        self.add_within(other);
+    }
+
+    /// Adds the heights of `other`, which lies within the patch
+    fn add_within(&mut self, other: &DisturbancePatch) {
+        let width = self.x.len();
+        for (y, row) in other.rows() {
+            let start = (y - self.y.start) * width + other.x.start - self.x.start;
+            for (height, added) in self.heights[start..].iter_mut().zip(row) {
+                *height += added;
+            }
+        }
+    }
+}
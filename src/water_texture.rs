@@ -1,34 +1,55 @@
-use crate::{render::gl_texture::GlTexture, water::Water};
+use crate::{
+    render::gl_texture::GlTexture, simulation::Simulation, water::Water,
+    water_backend::WaterBackend,
+};
 
+/// CPU water backend, uploads the normals and heights of the simulation's `Water`
 pub struct WaterTexture<'gl> {
-    water: Water,
     normal_texture: GlTexture<'gl>,
+    height_texture: GlTexture<'gl>,
 }
 
 impl<'gl> WaterTexture<'gl> {
-    pub fn new(gl: &'gl glow::Context, width: usize, wave_speed: f32) -> Self {
-        let water = Water::new(width, wave_speed);
+    pub fn new(gl: &'gl glow::Context, water: &Water) -> Self {
         Self {
             normal_texture: GlTexture::new(gl, &water.normal_texture()),
-            water,
+            height_texture: GlTexture::from_r32f(
+                gl,
+                water.width() as u32,
+                water.depth() as u32,
+                water.heights(),
+            ),
         }
     }
 
-    pub fn wave_speed_mut(&mut self) -> &mut f32 {
-        &mut self.water.wave_speed
+    /// Uploads `heights`, laid out like the heights of `water`, and their normals
+    pub fn load(&mut self, water: &Water, heights: &[f32]) {
+        let texture = water.normal_texture_of(heights);
+        self.normal_texture.load(&texture);
+        self.height_texture
+            .load_r32f(water.width() as u32, water.depth() as u32, heights);
     }
 
-    pub fn update(&mut self) {
-        self.water.update();
-        let texture = self.water.normal_texture();
-        self.normal_texture.load(&texture)
+    pub fn normal_texture(&self) -> &GlTexture<'gl> {
+        &self.normal_texture
     }
 
-    pub fn normal_texture(&self) -> &GlTexture {
-        &self.normal_texture
+    pub fn height_texture(&self) -> &GlTexture<'gl> {
+        &self.height_texture
+    }
+}
+
+impl WaterBackend for WaterTexture<'_> {
+    fn update(&mut self, simulation: &mut Simulation) {
+        let (water, heights) = simulation.displayed_water();
+        self.load(water, heights);
+    }
+
+    fn bind_normal_texture(&self) {
+        self.normal_texture.bind();
     }
 
-    pub fn disturb(&mut self, x: isize, y: isize, height: f32) {
-        self.water.disturb(x, y, height);
+    fn bind_height_texture(&self) {
+        self.height_texture.bind();
     }
 }
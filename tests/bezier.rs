@@ -0,0 +1,78 @@
+use duck::math::geometry::{bezier::BezierBSpline, curve::Curve3};
+use nalgebra::Point3;
+
+fn spline(points: &[[f32; 3]]) -> BezierBSpline {
+    BezierBSpline::through_points(points.iter().map(|p| Point3::from(*p)).collect())
+}
+
+/// Parabola y = x^2 for x in [0, 1], the de Boor points are the polar forms of x and x^2
+fn parabola() -> BezierBSpline {
+    spline(&[
+        [-1.0, 2.0 / 3.0, 0.0],
+        [0.0, -1.0 / 3.0, 0.0],
+        [1.0, 2.0 / 3.0, 0.0],
+        [2.0, 11.0 / 3.0, 0.0],
+    ])
+}
+
+#[test]
+fn unevenly_parameterized_line_has_its_straight_length() {
+    // Monotone de Boor points keep the curve on the x axis without turning back
+    let line = spline(&[
+        [-3.0, 0.0, 0.0],
+        [-1.0, 0.0, 0.0],
+        [0.0, 0.0, 0.0],
+        [4.0, 0.0, 0.0],
+        [5.0, 0.0, 0.0],
+        [9.0, 0.0, 0.0],
+    ]);
+    let expected = line.value(1.0).x - line.value(0.0).x;
+
+    assert_eq!(line.segment_count(), 3);
+    assert!(
+        (line.length() - expected).abs() < 1.0e-4,
+        "{}",
+        line.length()
+    );
+
+    let half = line.arc_length(0.5);
+    assert!((half - (line.value(0.5).x - line.value(0.0).x)).abs() < 1.0e-4);
+}
+
+#[test]
+fn parabola_has_its_analytic_length() {
+    let parabola = parabola();
+    assert!((parabola.value(0.5) - Point3::new(0.5, 0.25, 0.0)).norm() < 1.0e-6);
+
+    // Integral of sqrt(1 + 4x^2) from 0 to x
+    let length = |x: f32| 0.5 * x * (1.0 + 4.0 * x * x).sqrt() + 0.25 * (2.0 * x).asinh();
+
+    assert!((parabola.length() - length(1.0)).abs() < 1.0e-5);
+    assert!((parabola.arc_length(0.3) - length(0.3)).abs() < 1.0e-5);
+}
+
+#[test]
+fn parameter_at_length_inverts_arc_length() {
+    let curve = spline(&[
+        [0.0, 0.0, 0.0],
+        [1.0, 2.0, 0.0],
+        [3.0, -1.0, 1.0],
+        [4.0, 0.0, 3.0],
+        [2.0, 1.0, 5.0],
+    ]);
+    let total = curve.length();
+
+    for i in 0..=20 {
+        let length = total * i as f32 / 20.0;
+        let t = curve.parameter_at_length(length);
+        assert!(
+            (curve.arc_length(t) - length).abs() < 1.0e-4 * total,
+            "length {} at t {}",
+            length,
+            t
+        );
+    }
+
+    assert_eq!(curve.parameter_at_length(-1.0), 0.0);
+    assert_eq!(curve.parameter_at_length(2.0 * total), 1.0);
+}
@@ -0,0 +1,196 @@
+use duck::math::bspline::{BSpline, CubicBSpline};
+use nalgebra::{Vector2, Vector3};
+
+const SAMPLES: usize = 50;
+
+fn curve() -> BSpline<3> {
+    BSpline::new(
+        3,
+        vec![0.0, 0.0, 0.5, 1.0, 1.5, 3.0, 3.0, 4.0, 5.0, 5.5],
+        vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 2.0, 0.0),
+            Vector3::new(3.0, -1.0, 1.0),
+            Vector3::new(4.0, 0.0, 3.0),
+            Vector3::new(2.0, 1.0, 5.0),
+            Vector3::new(0.0, 3.0, 4.0),
+        ],
+    )
+}
+
+fn samples(spline: &BSpline<3>) -> impl Iterator<Item = f32> {
+    let domain = spline.domain();
+    (0..=SAMPLES)
+        .map(move |i| domain.start + (domain.end - domain.start) * i as f32 / SAMPLES as f32)
+}
+
+#[test]
+fn uniform_cubic_matches_the_bezier_conversion() {
+    let deboor = [0.0, 3.0, -3.0, 6.0, 1.0];
+    let spline = CubicBSpline::with_coefficients(deboor.to_vec());
+
+    // Segment ends are averages 1/6, 4/6, 1/6 of three consecutive de Boor points, the inner
+    // Bézier points divide the de Boor polygon into thirds
+    let expected = [
+        (deboor[0] + 4.0 * deboor[1] + deboor[2]) / 6.0,
+        (2.0 * deboor[1] + deboor[2]) / 3.0,
+        (deboor[1] + 2.0 * deboor[2]) / 3.0,
+        (deboor[1] + 4.0 * deboor[2] + deboor[3]) / 6.0,
+        (2.0 * deboor[2] + deboor[3]) / 3.0,
+        (deboor[2] + 2.0 * deboor[3]) / 3.0,
+        (deboor[2] + 4.0 * deboor[3] + deboor[4]) / 6.0,
+    ];
+
+    let bernstein = spline.bernstein_values();
+    assert_eq!(bernstein.len(), expected.len());
+    for (value, expected) in bernstein.iter().zip(expected) {
+        assert!(
+            (value - expected).abs() < 1.0e-5,
+            "{} != {}",
+            value,
+            expected
+        );
+    }
+
+    assert!((spline.value(0.0) - expected[0]).abs() < 1.0e-5);
+    assert!((spline.value(0.5) - expected[3]).abs() < 1.0e-5);
+    assert!((spline.value(1.0) - expected[6]).abs() < 1.0e-5);
+}
+
+#[test]
+fn cubic_spline_with_few_points_lowers_its_degree() {
+    let line = CubicBSpline::with_coefficients(vec![1.0, 3.0]);
+    assert_eq!(line.segment_count(), 1);
+    assert!((line.value(0.25) - 1.5).abs() < 1.0e-6);
+    assert!((line.derivative(0.7) - 2.0).abs() < 1.0e-5);
+
+    let point = CubicBSpline::with_coefficients(vec![2.0]);
+    assert_eq!(point.value(0.5), 2.0);
+    assert_eq!(point.derivative(0.5), 0.0);
+}
+
+#[test]
+fn clamped_spline_interpolates_its_ends() {
+    let points = vec![
+        Vector2::new(0.0, 0.0),
+        Vector2::new(1.0, 2.0),
+        Vector2::new(3.0, 2.0),
+        Vector2::new(4.0, 0.0),
+        Vector2::new(6.0, 1.0),
+    ];
+    let spline = BSpline::clamped(3, points.clone());
+
+    assert_eq!(
+        spline.knots(),
+        [0.0, 0.0, 0.0, 0.0, 1.0, 2.0, 2.0, 2.0, 2.0]
+    );
+    assert_eq!(spline.segment_count(), 2);
+    assert!((spline.value(0.0) - points[0]).norm() < 1.0e-6);
+    assert!((spline.value(2.0) - points[4]).norm() < 1.0e-6);
+
+    // The end tangents point along the first and last legs of the control polygon
+    assert!((spline.derivative(0.0) - 3.0 * (points[1] - points[0])).norm() < 1.0e-5);
+    assert!((spline.derivative(2.0) - 3.0 * (points[4] - points[3])).norm() < 1.0e-5);
+}
+
+#[test]
+fn derivative_matches_finite_differences() {
+    const H: f32 = 1.0e-3;
+
+    let polynomial = curve();
+    let rational = curve().with_weights(vec![1.0, 2.0, 0.5, 1.0, 3.0, 1.0]);
+
+    for spline in [polynomial, rational] {
+        let domain = spline.domain();
+        for u in samples(&spline).filter(|u| *u > domain.start + H && *u < domain.end - H) {
+            let difference = (spline.value(u + H) - spline.value(u - H)) / (2.0 * H);
+            let derivative = spline.derivative(u);
+            assert!(
+                (difference - derivative).norm() < 1.0e-2 * derivative.norm().max(1.0),
+                "{} != {} at {}",
+                difference,
+                derivative,
+                u
+            );
+
+            let difference = (spline.derivative(u + H) - spline.derivative(u - H)) / (2.0 * H);
+            let second_derivative = spline.second_derivative(u);
+            assert!(
+                (difference - second_derivative).norm()
+                    < 1.0e-2 * second_derivative.norm().max(1.0),
+                "{} != {} at {}",
+                difference,
+                second_derivative,
+                u
+            );
+        }
+    }
+}
+
+#[test]
+fn knot_insertion_keeps_the_curve() {
+    for weights in [None, Some(vec![1.0, 2.0, 0.5, 1.0, 3.0, 1.0])] {
+        let original = match weights {
+            Some(weights) => curve().with_weights(weights),
+            None => curve(),
+        };
+
+        let mut refined = original.clone();
+        for knot in [0.25, 1.0, 2.0, 3.0, 3.5] {
+            refined.insert_knot(knot);
+        }
+
+        assert_eq!(
+            refined.control_points().len(),
+            original.control_points().len() + 5
+        );
+        for u in samples(&original) {
+            assert!(
+                (refined.value(u) - original.value(u)).norm() < 1.0e-5,
+                "{} != {} at {}",
+                refined.value(u),
+                original.value(u),
+                u
+            );
+        }
+    }
+}
+
+#[test]
+fn nurbs_quarter_circle_stays_on_the_circle() {
+    let circle = BSpline::clamped(
+        2,
+        vec![
+            Vector2::new(1.0, 0.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(0.0, 1.0),
+        ],
+    )
+    .with_weights(vec![1.0, std::f32::consts::FRAC_1_SQRT_2, 1.0]);
+
+    for i in 0..=SAMPLES {
+        let u = i as f32 / SAMPLES as f32;
+        let point = circle.value(u);
+        assert!((point.norm() - 1.0).abs() < 1.0e-6, "{} at {}", point, u);
+
+        // Tangent to a circle around the origin
+        assert!(point.dot(&circle.derivative(u)).abs() < 1.0e-5);
+    }
+}
+
+#[test]
+fn bezier_points_of_a_single_segment_are_its_control_points() {
+    let points = vec![
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(1.0, 2.0, 0.0),
+        Vector3::new(3.0, -1.0, 1.0),
+        Vector3::new(4.0, 0.0, 3.0),
+    ];
+    let spline = BSpline::clamped(3, points.clone());
+
+    let bezier = spline.bezier_points();
+    assert_eq!(bezier.len(), points.len());
+    for (bezier, point) in bezier.iter().zip(&points) {
+        assert!((bezier - point).norm() < 1.0e-6);
+    }
+}
@@ -0,0 +1,60 @@
+use duck::{camera::Camera, math::affine::screen};
+use glutin::dpi::{PhysicalPosition, PhysicalSize};
+use nalgebra::{Point2, Point3};
+
+const HEIGHT: f32 = 4.0;
+
+/// Camera above the origin looking straight down, with a 90 degree field of view
+fn camera_looking_down() -> Camera {
+    let mut camera = Camera::new();
+    camera.position = Point3::new(0.0, HEIGHT, 0.0);
+    camera.angle_x = -std::f32::consts::FRAC_PI_2;
+    camera.resolution = PhysicalSize::new(800, 400);
+    camera
+}
+
+fn assert_close(a: Point3<f32>, b: Point3<f32>) {
+    assert!((a - b).norm() < 1.0e-4, "{} != {}", a, b);
+}
+
+#[test]
+fn screen_center_hits_the_plane_below_the_camera() {
+    let camera = camera_looking_down();
+    let center = screen::screen_to_ndc(&camera.resolution, &PhysicalPosition::new(400, 200));
+
+    let hit = camera.ray(center).intersect_horizontal_plane(0.0).unwrap();
+    assert_close(hit, Point3::origin());
+}
+
+#[test]
+fn screen_edges_hit_the_plane_at_the_field_of_view() {
+    let camera = camera_looking_down();
+
+    // The view is twice as wide as high and tan(fov / 2) = 1
+    let top = camera.ray(Point2::new(0.0, 1.0));
+    let right = camera.ray(Point2::new(1.0, 0.0));
+
+    assert_close(
+        top.intersect_horizontal_plane(0.0).unwrap(),
+        Point3::new(0.0, 0.0, -HEIGHT),
+    );
+    assert_close(
+        right.intersect_horizontal_plane(-1.0).unwrap(),
+        Point3::new(2.0 * (HEIGHT + 1.0), -1.0, 0.0),
+    );
+}
+
+#[test]
+fn plane_behind_the_ray_is_missed() {
+    let mut camera = camera_looking_down();
+    camera.angle_x = 0.0;
+
+    assert!(camera
+        .ray(Point2::new(0.0, 0.5))
+        .intersect_horizontal_plane(0.0)
+        .is_none());
+    assert!(camera
+        .ray(Point2::new(0.0, 0.0))
+        .intersect_horizontal_plane(0.0)
+        .is_none());
+}
@@ -0,0 +1,231 @@
+use duck::math::{
+    bspline::BSpline,
+    geometry::{
+        bezier::{BezierBSpline, BezierChain},
+        curve::{BoundingBox, Curve3},
+        hermite::HermiteSpline,
+    },
+};
+use nalgebra::{Point3, Vector3};
+
+const SAMPLES: usize = 50;
+
+fn points() -> Vec<Point3<f32>> {
+    vec![
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(1.0, 2.0, 0.0),
+        Point3::new(3.0, -1.0, 1.0),
+        Point3::new(4.0, 0.0, 3.0),
+        Point3::new(2.0, 1.0, 5.0),
+        Point3::new(0.0, 3.0, 4.0),
+    ]
+}
+
+/// One curve of every kind
+fn curves() -> Vec<Box<dyn Curve3>> {
+    vec![
+        Box::new(BSpline::new(
+            3,
+            vec![0.0, 0.0, 0.5, 1.0, 1.5, 3.0, 3.0, 4.0, 5.0, 5.5],
+            points().into_iter().map(|point| point.coords).collect(),
+        )),
+        Box::new(BezierBSpline::through_points(points())),
+        Box::new(BezierChain::new(points()[..4].to_vec())),
+        Box::new(HermiteSpline::catmull_rom(points())),
+        Box::new(HermiteSpline::kochanek_bartels(points(), 0.3, -0.4, 0.5)),
+    ]
+}
+
+fn samples(curve: &dyn Curve3) -> impl Iterator<Item = f32> {
+    let domain = curve.domain();
+    (0..=SAMPLES)
+        .map(move |i| domain.start + (domain.end - domain.start) * i as f32 / SAMPLES as f32)
+}
+
+fn assert_close(a: Vector3<f32>, b: Vector3<f32>, tolerance: f32) {
+    assert!((a - b).norm() <= tolerance, "{} != {}", a, b);
+}
+
+#[test]
+fn bezier_chain_of_the_bernstein_points_is_the_same_curve() {
+    let spline = BezierBSpline::through_points(points());
+    let chain = BezierChain::new(spline.bernstein_points());
+
+    assert_eq!(chain.segment_count(), spline.segment_count());
+    let segments = chain.segment_count() as f32;
+    for t in samples(&spline) {
+        assert_close(
+            chain.value(t * segments).coords,
+            spline.value(t).coords,
+            1.0e-5,
+        );
+        assert_close(
+            chain.derivative(t * segments) * segments,
+            spline.derivative(t),
+            1.0e-3,
+        );
+    }
+}
+
+#[test]
+fn interpolating_curves_pass_through_the_inner_points() {
+    let points = points();
+    let curves = [
+        HermiteSpline::catmull_rom(points.clone()),
+        HermiteSpline::kochanek_bartels(points.clone(), 0.5, 0.2, -0.3),
+    ];
+
+    for curve in curves {
+        assert_eq!(curve.domain(), 0.0..3.0);
+        for (i, point) in points[1..points.len() - 1].iter().enumerate() {
+            assert_close(curve.value(i as f32).coords, point.coords, 1.0e-5);
+        }
+    }
+}
+
+#[test]
+fn tcb_without_parameters_is_the_catmull_rom_spline_of_evenly_spaced_points() {
+    // Equal distances make the centripetal parameterization uniform
+    let points: Vec<_> = (0..7)
+        .map(|i| {
+            let angle = i as f32;
+            Point3::new(angle.cos(), 0.5 * angle, angle.sin())
+        })
+        .collect();
+
+    let catmull_rom = HermiteSpline::catmull_rom(points.clone());
+    let tcb = HermiteSpline::kochanek_bartels(points, 0.0, 0.0, 0.0);
+    for t in samples(&tcb) {
+        assert_close(tcb.value(t).coords, catmull_rom.value(t).coords, 1.0e-5);
+    }
+}
+
+#[test]
+fn derivatives_match_finite_differences() {
+    const H: f32 = 1.0e-3;
+
+    for curve in curves() {
+        let breakpoints = curve.breakpoints();
+        let smooth = |t: f32| breakpoints.iter().all(|b| (t - b).abs() > H);
+
+        for t in samples(curve.as_ref()).filter(|&t| smooth(t)) {
+            let derivative = curve.derivative(t);
+            let difference = (curve.value(t + H) - curve.value(t - H)) / (2.0 * H);
+            assert_close(difference, derivative, 1.0e-2 * derivative.norm().max(1.0));
+
+            let second_derivative = curve.second_derivative(t);
+            let difference = (curve.derivative(t + H) - curve.derivative(t - H)) / (2.0 * H);
+            assert_close(
+                difference,
+                second_derivative,
+                1.0e-2 * second_derivative.norm().max(1.0),
+            );
+        }
+    }
+}
+
+#[test]
+fn arc_length_adds_up_the_chords() {
+    const CHORDS: usize = 2000;
+
+    for curve in curves() {
+        let domain = curve.domain();
+        let chords: f32 = (0..CHORDS)
+            .map(|i| {
+                let t = |i: usize| {
+                    domain.start + (domain.end - domain.start) * i as f32 / CHORDS as f32
+                };
+                (curve.value(t(i + 1)) - curve.value(t(i))).norm()
+            })
+            .sum();
+
+        let length = curve.length();
+        assert!((length - chords).abs() < 1.0e-3 * length, "{:?}", curve);
+
+        for i in 0..=10 {
+            let length = length * i as f32 / 10.0;
+            let t = curve.parameter_at_length(length);
+            assert!((curve.arc_length(t) - length).abs() < 1.0e-3, "{:?}", curve);
+        }
+    }
+}
+
+#[test]
+fn bounding_box_is_tight() {
+    for curve in curves() {
+        let bounds = curve.bounding_box();
+        let mut sampled = None::<(Point3<f32>, Point3<f32>)>;
+
+        for i in 0..=10 * SAMPLES {
+            let domain = curve.domain();
+            let t = domain.start + (domain.end - domain.start) * i as f32 / (10 * SAMPLES) as f32;
+            let point = curve.value(t);
+
+            let expanded = |point: &Point3<f32>| {
+                (0..3).all(|i| {
+                    bounds.min[i] - 1.0e-5 <= point[i] && point[i] <= bounds.max[i] + 1.0e-5
+                })
+            };
+            assert!(expanded(&point), "{} outside of {:?}", point, bounds);
+
+            sampled = Some(match sampled {
+                None => (point, point),
+                Some((min, max)) => (min.inf(&point), max.sup(&point)),
+            });
+        }
+
+        // Dense samples come close to every side of the box
+        let (min, max) = sampled.unwrap();
+        assert_close(min.coords, bounds.min.coords, 1.0e-3);
+        assert_close(max.coords, bounds.max.coords, 1.0e-3);
+    }
+}
+
+#[test]
+fn parabola_has_its_vertex_in_the_bounding_box() {
+    // y = (2t - 1)^2 - 1 on [0, 1] dips to -1 between its ends at 0
+    let parabola = BezierChain::new(vec![
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(1.0, -4.0 / 3.0, 0.0),
+        Point3::new(2.0, -4.0 / 3.0, 0.0),
+        Point3::new(3.0, 0.0, 0.0),
+    ]);
+
+    let bounds = parabola.bounding_box();
+    assert_close(bounds.min.coords, Vector3::new(0.0, -1.0, 0.0), 1.0e-6);
+    assert_close(bounds.max.coords, Vector3::new(3.0, 0.0, 0.0), 1.0e-6);
+}
+
+#[test]
+fn confined_hermite_spline_stays_within_the_bounds() {
+    // Catmull-Rom splines overshoot points close to the edge of the box
+    let points = vec![
+        Point3::new(-1.0, 0.0, -1.0),
+        Point3::new(1.0, 0.0, -1.0),
+        Point3::new(1.0, 0.0, 1.0),
+        Point3::new(-1.0, 0.0, 1.0),
+        Point3::new(-1.0, 0.0, -1.0),
+        Point3::new(1.0, 0.0, -1.0),
+    ];
+    let bounds = BoundingBox::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+
+    let spline = HermiteSpline::catmull_rom(points.clone());
+    assert!(!bounds.contains(&spline.bounding_box().max));
+
+    let mut confined = spline.clone();
+    confined.confine(&bounds);
+    let inside = confined.bounding_box();
+    assert!((0..3).all(|i| {
+        bounds.min[i] - 1.0e-5 <= inside.min[i] && inside.max[i] <= bounds.max[i] + 1.0e-5
+    }));
+
+    // Still through the same points, and as smooth
+    for (i, point) in points[1..points.len() - 1].iter().enumerate() {
+        assert_close(confined.value(i as f32).coords, point.coords, 1.0e-6);
+    }
+    for t in confined.breakpoints() {
+        let before = confined.derivative(t - 1.0e-4);
+        let after = confined.derivative(t + 1.0e-4);
+        assert_close(before, after, 1.0e-2 * before.norm().max(1.0));
+    }
+}
@@ -0,0 +1,56 @@
+use duck::fixed_timestep::FixedTimestep;
+use std::time::Duration;
+
+fn assert_close(a: f32, b: f32) {
+    assert!((a - b).abs() < 1.0e-5, "{} != {}", a, b);
+}
+
+#[test]
+fn leftover_time_carries_over_to_the_next_frame() {
+    let mut timestep = FixedTimestep::new(10.0, 100);
+
+    assert_eq!(timestep.advance(Duration::from_millis(250)), 2);
+    assert_close(timestep.alpha(), 0.5);
+
+    // The half step left over and another 0.6 make a whole one
+    assert_eq!(timestep.advance(Duration::from_millis(60)), 1);
+    assert_close(timestep.alpha(), 0.1);
+
+    assert_eq!(timestep.advance(Duration::from_millis(30)), 0);
+    assert_close(timestep.alpha(), 0.4);
+}
+
+#[test]
+fn steps_above_the_cap_are_dropped() {
+    let mut timestep = FixedTimestep::new(100.0, 5);
+
+    // 10.5 steps are due, 5 are taken and the whole ones over the cap are not made up later
+    assert_eq!(timestep.advance(Duration::from_micros(105_000)), 5);
+    assert_close(timestep.alpha(), 0.5);
+    assert_eq!(timestep.advance(Duration::ZERO), 0);
+    assert_eq!(timestep.advance(Duration::from_millis(6)), 1);
+}
+
+#[test]
+fn alpha_is_the_fraction_of_a_step_not_yet_taken() {
+    let mut timestep = FixedTimestep::new(240.0, 10);
+    assert_eq!(timestep.alpha(), 0.0);
+
+    for frame in 1..=3 {
+        assert_eq!(timestep.advance(Duration::from_secs_f32(0.25 / 240.0)), 0);
+        assert_close(timestep.alpha(), 0.25 * frame as f32);
+    }
+}
+
+#[test]
+fn changing_the_rate_keeps_the_accumulated_time() {
+    let mut timestep = FixedTimestep::new(10.0, 10);
+    timestep.advance(Duration::from_millis(50));
+    assert_close(timestep.alpha(), 0.5);
+
+    // 50 ms are half a step at 10 Hz and a quarter of one at 5 Hz
+    timestep.set_rate(5.0);
+    assert_close(timestep.alpha(), 0.25);
+    assert_eq!(timestep.advance(Duration::from_millis(160)), 1);
+    assert_close(timestep.alpha(), 0.05);
+}
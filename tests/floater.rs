@@ -0,0 +1,91 @@
+use duck::floater::Floater;
+use nalgebra::{Point2, Vector2};
+
+const DELTA: f32 = 1.0 / 60.0;
+const SETTLE_STEPS: usize = 600;
+const SLOPE: f32 = 0.1;
+
+fn duck_floater() -> Floater {
+    Floater::new(Floater::elliptic_hull(0.9, 0.35, 3), 0.15, 0.3)
+}
+
+fn settle(floater: &mut Floater, surface: impl Fn(Point2<f32>) -> f32) {
+    for _ in 0..SETTLE_STEPS {
+        floater.step(DELTA, Point2::origin(), Vector2::x(), &surface);
+    }
+}
+
+#[test]
+fn floats_at_rest_on_flat_water() {
+    let mut floater = duck_floater();
+    settle(&mut floater, |_| 0.0);
+
+    assert!(floater.heave.abs() < 1.0e-5, "heave {}", floater.heave);
+    assert!(floater.pitch.abs() < 1.0e-5 && floater.roll.abs() < 1.0e-5);
+}
+
+#[test]
+fn dropped_floater_bobs_and_settles() {
+    let mut floater = duck_floater();
+    floater.heave = 0.1;
+
+    let mut lowest = f32::MAX;
+    for _ in 0..SETTLE_STEPS {
+        floater.step(DELTA, Point2::origin(), Vector2::x(), |_| 0.0);
+        lowest = lowest.min(floater.heave);
+    }
+
+    // Underdamped, so it sinks below its rest height before settling
+    assert!(lowest < -0.01, "lowest heave {}", lowest);
+    assert!(floater.heave.abs() < 1.0e-4, "heave {}", floater.heave);
+}
+
+#[test]
+fn pitches_along_a_forward_slope() {
+    let mut floater = duck_floater();
+    settle(&mut floater, |point| SLOPE * point.x);
+
+    assert!(
+        (floater.pitch - SLOPE).abs() < 1.0e-3,
+        "pitch {}",
+        floater.pitch
+    );
+    assert!(floater.roll.abs() < 1.0e-6 && floater.heave.abs() < 1.0e-4);
+}
+
+#[test]
+fn rolls_along_a_sideways_slope() {
+    let mut floater = duck_floater();
+    settle(&mut floater, |point| SLOPE * point.y);
+
+    assert!(
+        (floater.roll - SLOPE).abs() < 1.0e-3,
+        "roll {}",
+        floater.roll
+    );
+    assert!(floater.pitch.abs() < 1.0e-6 && floater.heave.abs() < 1.0e-4);
+}
+
+#[test]
+fn hull_follows_heading() {
+    let floater = duck_floater();
+    let center = Point2::new(1.0, 2.0);
+
+    let straight: Vec<_> = floater.hull_positions(center, Vector2::x()).collect();
+    let turned: Vec<_> = floater.hull_positions(center, Vector2::y()).collect();
+
+    for (straight, turned) in straight.iter().zip(&turned) {
+        let offset = straight - center;
+        let rotated = Vector2::new(-offset.y, offset.x);
+        assert!((turned - center - rotated).norm() < 1.0e-6);
+    }
+}
+
+#[test]
+fn mass_matches_displaced_water() {
+    let floater = duck_floater();
+    let area: f32 = floater.hull().iter().map(|point| point.area).sum();
+
+    assert!((area - std::f32::consts::PI * 0.9 * 0.35).abs() < 1.0e-5);
+    assert!((floater.mass() - Floater::WATER_DENSITY * area * 0.15).abs() < 1.0e-2);
+}
@@ -0,0 +1,81 @@
+use duck::{
+    gpu_water::GpuWater,
+    simulation::{Simulation, WaterEvent},
+};
+use glutin::{
+    dpi::PhysicalSize,
+    event_loop::{EventLoop, EventLoopBuilder},
+    platform::unix::EventLoopBuilderExtUnix,
+    Api, Context, ContextBuilder, GlProfile, GlRequest, PossiblyCurrent,
+};
+use std::time::Duration;
+
+const SEED: u64 = 5;
+const DELTA: Duration = Duration::from_millis(16);
+const FRAMES: usize = 30;
+
+/// OpenGL 4.3 context for the compute shaders, `None` without a display to create it on
+fn gl_context() -> Option<(EventLoop<()>, Context<PossiblyCurrent>, glow::Context)> {
+    if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+        return None;
+    }
+
+    let event_loop = EventLoopBuilder::new().with_any_thread(true).build();
+    let context = ContextBuilder::new()
+        .with_gl(GlRequest::Specific(Api::OpenGl, (4, 3)))
+        .with_gl_profile(GlProfile::Core)
+        .build_headless(&event_loop, PhysicalSize::new(1, 1))
+        .ok()?;
+    let context = unsafe { context.make_current() }.ok()?;
+
+    let gl = unsafe { glow::Context::from_loader_function(|s| context.get_proc_address(s).cast()) };
+    Some((event_loop, context, gl))
+}
+
+#[test]
+fn gpu_water_matches_cpu_water() {
+    let Some((_event_loop, _context, gl)) = gl_context() else {
+        eprintln!("No OpenGL 4.3 context, skipping the GPU water test");
+        return;
+    };
+
+    let mut simulation = Simulation::new(SEED);
+    simulation.record_water_events();
+    let mut cpu = simulation.water().clone();
+    let mut gpu = GpuWater::new(&gl, simulation.water());
+
+    for _ in 0..FRAMES {
+        simulation.step(DELTA);
+        let events = simulation.take_water_events();
+        for event in &events {
+            match event {
+                WaterEvent::Stamp(disturbance) => cpu.stamp(disturbance),
+                WaterEvent::Update => cpu.update().unwrap(),
+            }
+        }
+        gpu.replay(simulation.water(), &events);
+    }
+
+    let mut read_back = simulation.water().clone();
+    gpu.read_back(&mut read_back);
+
+    let max_height = cpu
+        .heights()
+        .iter()
+        .fold(0.0, |max, h| f32::max(max, h.abs()));
+    assert!(max_height > 0.0);
+    for (cpu, gpu) in [
+        (cpu.heights(), read_back.heights()),
+        (cpu.previous_heights(), read_back.previous_heights()),
+    ] {
+        for (idx, (cpu, gpu)) in cpu.iter().zip(gpu).enumerate() {
+            assert!(
+                (cpu - gpu).abs() <= 1.0e-4 * max_height,
+                "sample {}: {} != {}",
+                idx,
+                cpu,
+                gpu
+            );
+        }
+    }
+}
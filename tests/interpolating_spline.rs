@@ -0,0 +1,134 @@
+use duck::math::{
+    geometry::{bezier::BezierBSpline, curve::Curve3},
+    interpolating_spline::{EndCondition, InterpolatingSpline},
+};
+use nalgebra::{Point3, Vector1, Vector3};
+
+const SAMPLES: usize = 40;
+
+fn scalar(values: &[f32]) -> Vec<Vector1<f32>> {
+    values.iter().map(|&value| Vector1::new(value)).collect()
+}
+
+fn assert_close(a: f32, b: f32, tolerance: f32) {
+    assert!((a - b).abs() <= tolerance, "{} != {}", a, b);
+}
+
+#[test]
+fn natural_spline_through_three_points_has_its_closed_form() {
+    let spline = InterpolatingSpline::uniform(scalar(&[0.0, 1.0, 0.0]), EndCondition::Natural);
+
+    // 4 M1 = 6 (-1 - 1) gives M1 = -3, so S(t) = 1.5 t - 0.5 t^3 on [0, 1]
+    for i in 0..=SAMPLES {
+        let t = i as f32 / SAMPLES as f32;
+        assert_close(spline.value(t).x, 1.5 * t - 0.5 * t * t * t, 1.0e-6);
+        assert_close(spline.derivative(t).x, 1.5 - 1.5 * t * t, 1.0e-5);
+        assert_close(spline.value(2.0 - t).x, spline.value(t).x, 1.0e-6);
+    }
+
+    assert_close(spline.second_derivative(0.0).x, 0.0, 1.0e-6);
+    assert_close(spline.second_derivative(1.0).x, -3.0, 1.0e-5);
+    assert_close(spline.second_derivative(2.0).x, 0.0, 1.0e-6);
+}
+
+#[test]
+fn clamped_spline_reproduces_a_cubic() {
+    let cubic = |t: f32| t * t * t - 2.0 * t * t + t + 1.0;
+    let derivative = |t: f32| 3.0 * t * t - 4.0 * t + 1.0;
+    let parameters = vec![0.0, 0.7, 1.5, 2.0, 3.0];
+
+    let spline = InterpolatingSpline::new(
+        parameters.clone(),
+        scalar(&parameters.iter().map(|&t| cubic(t)).collect::<Vec<_>>()),
+        EndCondition::Clamped(Vector1::new(derivative(0.0)), Vector1::new(derivative(3.0))),
+    );
+
+    for i in 0..=SAMPLES {
+        let t = 3.0 * i as f32 / SAMPLES as f32;
+        assert_close(spline.value(t).x, cubic(t), 1.0e-4);
+        assert_close(spline.derivative(t).x, derivative(t), 1.0e-3);
+        assert_close(spline.second_derivative(t).x, 6.0 * t - 4.0, 1.0e-3);
+    }
+}
+
+#[test]
+fn natural_spline_through_a_line_is_straight() {
+    let points = vec![
+        Vector3::new(0.0, 1.0, 2.0),
+        Vector3::new(1.0, 3.0, 1.0),
+        Vector3::new(2.0, 5.0, 0.0),
+        Vector3::new(3.0, 7.0, -1.0),
+    ];
+    let spline = InterpolatingSpline::uniform(points, EndCondition::Natural);
+
+    for i in 0..=SAMPLES {
+        let t = 3.0 * i as f32 / SAMPLES as f32;
+        assert!((spline.value(t) - Vector3::new(t, 1.0 + 2.0 * t, 2.0 - t)).norm() < 1.0e-5);
+        assert!(spline.second_derivative(t).norm() < 1.0e-5);
+    }
+}
+
+#[test]
+fn periodic_spline_through_a_sine_has_its_closed_form_curvature() {
+    const COUNT: usize = 8;
+    let h = std::f32::consts::TAU / COUNT as f32;
+    let nodes: Vec<_> = (0..=COUNT).map(|i| i as f32 * h).collect();
+    let values: Vec<_> = nodes[..COUNT].iter().map(|t| t.sin()).collect();
+
+    let spline = InterpolatingSpline::new(nodes.clone(), scalar(&values), EndCondition::Periodic);
+
+    // Inserting M = -c sin into the system of the second derivatives at equal spacing
+    let c = 6.0 * (1.0 - h.cos()) / (h * h * (2.0 + h.cos()));
+    for (node, value) in nodes.iter().zip(&values) {
+        assert_close(spline.value(*node).x, *value, 1.0e-6);
+        assert_close(spline.second_derivative(*node).x, -c * value, 1.0e-4);
+    }
+
+    // The ends join smoothly and the curve repeats
+    let end = std::f32::consts::TAU;
+    assert_close(
+        spline.derivative(0.0).x,
+        spline.derivative(end - 1.0e-6).x,
+        1.0e-4,
+    );
+    assert_close(spline.value(0.3).x, spline.value(0.3 + end).x, 1.0e-5);
+    assert_close(spline.value(0.3).x, spline.value(0.3 - end).x, 1.0e-5);
+}
+
+#[test]
+fn de_boor_points_give_the_same_curve() {
+    let points = vec![
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(2.0, 1.0, 0.0),
+        Vector3::new(3.0, -1.0, 1.0),
+        Vector3::new(1.0, 0.0, 3.0),
+        Vector3::new(-1.0, 2.0, 2.0),
+    ];
+
+    for end in [EndCondition::Natural, EndCondition::Periodic] {
+        let spline = InterpolatingSpline::uniform(points.clone(), end);
+        let mut deboor: Vec<_> = spline
+            .deboor_points()
+            .into_iter()
+            .map(Point3::from)
+            .collect();
+        // The curve is at a point in the middle of the de Boor points around it
+        if spline.is_periodic() {
+            deboor.insert(0, *deboor.last().unwrap());
+            deboor.extend_from_within(1..3);
+        }
+
+        let end = *spline.parameters().last().unwrap();
+        let bspline = BezierBSpline::through_points(deboor);
+        for i in 0..=SAMPLES {
+            let t = i as f32 / SAMPLES as f32;
+            assert!(
+                (bspline.value(t).coords - spline.value(t * end)).norm() < 1.0e-4,
+                "{} != {} at {}",
+                bspline.value(t),
+                spline.value(t * end),
+                t
+            );
+        }
+    }
+}
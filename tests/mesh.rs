@@ -0,0 +1,41 @@
+use duck::{
+    primitives::vertex::{DuckVertex, SimpleVertex},
+    render::mesh::Mesh,
+};
+
+#[test]
+fn grid_spans_unit_square_facing_up() {
+    let grid = Mesh::<SimpleVertex>::grid(4, 3);
+
+    assert_eq!(grid.vertices.len(), 12);
+    assert_eq!(grid.triangles.len(), 3 * 2 * 2);
+
+    let first = grid.vertices.first().unwrap().0;
+    let last = grid.vertices.last().unwrap().0;
+    assert_eq!((first.x, first.y, first.z), (0.0, 0.0, 0.0));
+    assert_eq!((last.x, last.y, last.z), (1.0, 0.0, 1.0));
+
+    assert!(grid
+        .triangles
+        .iter()
+        .flat_map(|triangle| triangle.0)
+        .all(|idx| (idx as usize) < grid.vertices.len()));
+
+    for triangle in &grid.triangles {
+        let [a, b, c] = triangle.0.map(|idx| grid.vertices[idx as usize].0);
+        assert!((b - a).cross(&(c - a)).y > 0.0, "{:?}", triangle.0);
+    }
+}
+
+#[test]
+fn bounding_radius_reaches_the_farthest_vertex() {
+    let model = "3\n\
+        1 0 0 0 1 0 0 0\n\
+        0 -2 0 0 1 0 0 0\n\
+        1 2 2 0 1 0 0 0\n\
+        1\n\
+        0 1 2";
+    let mesh = Mesh::<DuckVertex>::parse_model(model).unwrap();
+
+    assert_eq!(mesh.bounding_radius(), 3.0);
+}
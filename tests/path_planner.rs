@@ -0,0 +1,386 @@
+use duck::{
+    constants::DUCK_MODEL_PATH,
+    duck::Duck,
+    math::geometry::{bezier::BezierBSpline, curve::Curve3},
+    path_planner::{
+        FigureEight, FollowCursor, PathCurve, PathPlanner, PlannerContext, RandomWander,
+        WaypointLoop,
+    },
+    render::mesh::Mesh,
+    simulation::Simulation,
+    water::Water,
+};
+use nalgebra::{Point2, Point3, Vector2};
+use rand::{rngs::StdRng, SeedableRng};
+use std::path::Path;
+
+const HEIGHT: f32 = -2.7;
+const POND_ROUTE: &str = "routes/pond.route";
+
+/// Asks `planner` for `count` points after `points`, with the cursor at `cursor`
+fn plan(
+    planner: &mut dyn PathPlanner,
+    water: &Water,
+    mut points: Vec<Point3<f32>>,
+    cursor: Option<Point2<f32>>,
+    count: usize,
+) -> Vec<Point3<f32>> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut context = PlannerContext {
+        rng: &mut rng,
+        water,
+        height: HEIGHT,
+        bounds: 0.5 * water.extent(),
+        path_ends: &[],
+        cursor,
+        curve: PathCurve::BSpline,
+    };
+
+    for _ in 0..count {
+        let point = planner.next_point(&points, &mut context);
+        points.push(point);
+    }
+
+    points
+}
+
+fn square_route() -> WaypointLoop {
+    WaypointLoop::new(vec![
+        Point2::new(-1.0, -1.0),
+        Point2::new(1.0, -1.0),
+        Point2::new(1.0, 1.0),
+        Point2::new(-1.0, 1.0),
+    ])
+}
+
+/// Position at the start of the path through four consecutive de Boor points
+fn window_start(window: &[Point3<f32>]) -> Point2<f32> {
+    let start = BezierBSpline::through_points(window.to_vec()).value(0.0);
+    Point2::new(start.x, start.z)
+}
+
+#[test]
+fn waypoint_loop_passes_through_waypoints() {
+    let water = Simulation::default_water();
+    let mut route = WaypointLoop::new(vec![
+        Point2::new(-1.0, -1.0),
+        Point2::new(2.0, -1.5),
+        Point2::new(1.0, 1.0),
+        Point2::new(-2.0, 0.5),
+        Point2::new(-3.0, -2.0),
+    ]);
+    let waypoints = route.waypoints().to_vec();
+
+    let points = plan(&mut route, &water, Vec::new(), None, 12);
+
+    // Every path through four de Boor points starts at the waypoint of the second one
+    for (idx, window) in points.windows(4).enumerate() {
+        let waypoint = waypoints[(idx + 1) % waypoints.len()];
+        assert!(
+            (window_start(window) - waypoint).norm() < 1.0e-5,
+            "{} != {}",
+            window_start(window),
+            waypoint
+        );
+        assert!(window.iter().all(|point| point.y == HEIGHT));
+    }
+}
+
+#[test]
+fn waypoint_loop_can_start_at_any_waypoint() {
+    let water = Simulation::default_water();
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut context = PlannerContext {
+        rng: &mut rng,
+        water: &water,
+        height: HEIGHT,
+        bounds: 0.5 * water.extent(),
+        path_ends: &[],
+        cursor: None,
+        curve: PathCurve::CatmullRom,
+    };
+
+    let waypoints = square_route().waypoints().to_vec();
+    for start in 0..2 * waypoints.len() {
+        let mut route = square_route().starting_at(start);
+        let point = route.next_point(&[], &mut context);
+        let waypoint = waypoints[start % waypoints.len()];
+        assert_eq!(Point2::new(point.x, point.z), waypoint);
+    }
+}
+
+#[test]
+fn routes_the_ducks_cannot_reach_are_rejected() {
+    let bounds = Simulation::new(0).duck_bounds();
+    let pond = WaypointLoop::load(Path::new(POND_ROUTE)).unwrap();
+    assert!(pond.check_bounds(bounds).is_ok());
+
+    // Waypoints within the bounds, but the spline through them bulges out of them
+    let octagon = WaypointLoop::new(vec![
+        Point2::new(-3.5, -3.5),
+        Point2::new(0.0, -3.7),
+        Point2::new(3.5, -3.5),
+        Point2::new(3.7, 0.0),
+        Point2::new(3.5, 3.5),
+        Point2::new(0.0, 3.7),
+        Point2::new(-3.5, 3.5),
+        Point2::new(-3.7, 0.0),
+    ]);
+    assert!(octagon.check_bounds(bounds).is_err());
+}
+
+#[test]
+fn duck_passes_every_waypoint_of_the_pond_route() {
+    const DELTA: std::time::Duration = std::time::Duration::from_millis(10);
+    const STEPS: usize = 600;
+
+    let water = Water::new(64, 64, Simulation::WATER_EXTENT, 1.0, 1.0 / 240.0);
+    let mut simulation = Simulation::with_water(3, water);
+    while !simulation.ducks().is_empty() {
+        simulation.remove_duck(0);
+    }
+    simulation.set_duck_radius(duck_radius());
+
+    let route = WaypointLoop::load(Path::new(POND_ROUTE)).unwrap();
+    let waypoints = route.waypoints().to_vec();
+    simulation.add_duck_with(Box::new(route));
+    simulation.ducks_iter_mut().next().unwrap().speed = 5.0;
+
+    // A lap is about 21 m long, six seconds at 5 m/s
+    let mut closest = vec![f32::INFINITY; waypoints.len()];
+    for _ in 0..STEPS {
+        simulation.step(DELTA);
+
+        let position = simulation.ducks()[0].position();
+        for (closest, waypoint) in closest.iter_mut().zip(&waypoints) {
+            *closest = closest.min((position - waypoint).norm());
+        }
+    }
+
+    // Positions are 5 cm apart
+    for (closest, waypoint) in closest.iter().zip(&waypoints) {
+        assert!(*closest < 0.05, "{} missed by {}", waypoint, closest);
+    }
+}
+
+#[test]
+fn interpolating_curves_go_through_the_waypoints_themselves() {
+    let water = Simulation::default_water();
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut context = PlannerContext {
+        rng: &mut rng,
+        water: &water,
+        height: HEIGHT,
+        bounds: 0.5 * water.extent(),
+        path_ends: &[],
+        cursor: None,
+        curve: PathCurve::CatmullRom,
+    };
+
+    let mut route = square_route();
+    let points = route.initial_points(&mut context);
+    let path = context.build(points);
+
+    // The path runs from the second waypoint to the third
+    let waypoints = route.waypoints();
+    for (t, waypoint) in [(0.0, waypoints[1]), (1.0, waypoints[2])] {
+        let position = path.value(t);
+        assert!((Point2::new(position.x, position.z) - waypoint).norm() < 1.0e-5);
+    }
+}
+
+#[test]
+fn route_survives_saving_and_loading() {
+    let route = square_route();
+    let path = std::env::temp_dir().join(format!("duck_route_{}.route", std::process::id()));
+
+    route.save(&path).unwrap();
+    let loaded = WaypointLoop::load(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.unwrap(), route);
+}
+
+#[test]
+fn route_parsing_skips_comments_and_reports_bad_lines() {
+    let route: WaypointLoop = "# Comment\n0 1\n\n  2.5 -3  \n4 4\n".parse().unwrap();
+    assert_eq!(
+        route.waypoints(),
+        [
+            Point2::new(0.0, 1.0),
+            Point2::new(2.5, -3.0),
+            Point2::new(4.0, 4.0)
+        ]
+    );
+
+    assert!("0 1\n2\n3 3\n".parse::<WaypointLoop>().is_err());
+    assert!("0 1\nx 2\n3 3\n".parse::<WaypointLoop>().is_err());
+    assert!("0 1\n2 2\n".parse::<WaypointLoop>().is_err());
+}
+
+#[test]
+fn figure_eight_closes_within_its_extent() {
+    let water = Simulation::default_water();
+    let half_extent = Vector2::new(3.0, 2.0);
+    let mut planner = FigureEight::new(Point2::new(1.0, -1.0), half_extent);
+
+    let points = plan(&mut planner, &water, Vec::new(), None, 25);
+
+    // The loop repeats and crosses itself at the center
+    assert!((points[0] - points[12]).norm() < 1.0e-5);
+    assert!((points[0] - points[24]).norm() < 1.0e-5);
+    assert!((points[0] - Point3::new(1.0, HEIGHT, -1.0)).norm() < 1.0e-5);
+    assert!((points[6] - Point3::new(1.0, HEIGHT, -1.0)).norm() < 1.0e-5);
+
+    for point in points {
+        assert!((point.x - 1.0).abs() <= half_extent.x + 1.0e-5);
+        assert!((point.z + 1.0).abs() <= half_extent.y + 1.0e-5);
+    }
+}
+
+#[test]
+fn follow_cursor_approaches_and_circles_the_cursor() {
+    const STEP: f32 = 0.5;
+
+    let water = Simulation::default_water();
+    let cursor = Point2::new(3.0, 0.0);
+    let mut planner = FollowCursor::new(STEP);
+    let start = vec![Point3::new(-2.25, HEIGHT, 0.0)];
+
+    let points = plan(&mut planner, &water, start, Some(cursor), 30);
+
+    let distance = |point: &Point3<f32>| (Point2::new(point.x, point.z) - cursor).norm();
+    for pair in points.windows(2) {
+        assert!(((pair[1] - pair[0]).norm() - STEP).abs() < 1.0e-5);
+    }
+    assert!(distance(&points[10]) < STEP);
+    assert!(points[10..]
+        .iter()
+        .all(|point| distance(point) <= 2.0 * STEP));
+}
+
+#[test]
+fn duck_follows_the_route_of_its_planner() {
+    let mut simulation = Simulation::new(3);
+    while !simulation.ducks().is_empty() {
+        simulation.remove_duck(0);
+    }
+    simulation.add_duck_with(Box::new(square_route()));
+
+    let first = simulation.ducks()[0].path().deboor_points();
+    assert!((window_start(&first) - Point2::new(1.0, -1.0)).norm() < 1.0e-5);
+
+    // Long enough to move on to a new path
+    for _ in 0..120 {
+        simulation.step(std::time::Duration::from_millis(16));
+    }
+
+    let points = simulation.ducks()[0].path().deboor_points();
+    assert_ne!(points, first);
+    let start = window_start(&points);
+    assert!(
+        (start.x.abs() - 1.0).abs() < 1.0e-5 && (start.y.abs() - 1.0).abs() < 1.0e-5,
+        "{} is no waypoint",
+        start
+    );
+}
+
+fn duck_radius() -> f32 {
+    Mesh::from_file(Path::new(DUCK_MODEL_PATH)).bounding_radius() * Duck::SCALE
+}
+
+#[test]
+fn model_radius_contains_the_duck_model() {
+    let radius = duck_radius();
+    assert!(Duck::MODEL_RADIUS >= radius && Duck::MODEL_RADIUS < radius + 0.05);
+}
+
+#[test]
+fn new_simulation_keeps_the_ducks_a_model_radius_from_the_walls() {
+    let simulation = Simulation::new(5);
+    assert_eq!(simulation.duck_radius(), Duck::MODEL_RADIUS);
+
+    let half_extent = 0.5 * simulation.water().extent();
+    for duck in simulation.ducks() {
+        assert!(duck.points().iter().all(|point| {
+            point.x.abs() <= half_extent.x - Duck::MODEL_RADIUS
+                && point.z.abs() <= half_extent.y - Duck::MODEL_RADIUS
+        }));
+    }
+}
+
+#[test]
+fn random_paths_keep_the_duck_inside_the_pool() {
+    const SEEDS: u64 = 20;
+    const EXTENSIONS: usize = 50;
+    const SAMPLES: usize = 32;
+
+    let water = Simulation::default_water();
+    let radius = duck_radius();
+    let bounds = 0.5 * water.extent() - Vector2::repeat(radius);
+    assert!(radius > 0.0 && bounds.min() > 0.0);
+
+    let curves = [
+        PathCurve::BSpline,
+        PathCurve::CatmullRom,
+        PathCurve::Tcb {
+            tension: -0.5,
+            continuity: 0.3,
+            bias: 0.2,
+        },
+    ];
+
+    for curve in curves {
+        for seed in 0..SEEDS {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut context = PlannerContext {
+                rng: &mut rng,
+                water: &water,
+                height: HEIGHT,
+                bounds,
+                path_ends: &[],
+                cursor: None,
+                curve,
+            };
+
+            let mut planner = RandomWander;
+            let mut points = planner.initial_points(&mut context);
+            for _ in 0..EXTENSIONS {
+                let point = planner.next_point(&points[points.len() - 4..], &mut context);
+                points.push(point);
+            }
+
+            for window in points.windows(4) {
+                let path = context.build(window.to_vec());
+                let domain = path.domain();
+                for i in 0..=SAMPLES {
+                    let t = domain.start + (domain.end - domain.start) * i as f32 / SAMPLES as f32;
+                    let position = path.value(t);
+                    assert!(
+                        position.x.abs() + radius <= 0.5 * water.extent().x + 1.0e-5
+                            && position.z.abs() + radius <= 0.5 * water.extent().y + 1.0e-5,
+                        "{:?}, seed {}: duck at {} reaches the wall",
+                        curve,
+                        seed,
+                        position
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn ducks_are_replanned_within_their_radius() {
+    let mut simulation = Simulation::new(5);
+    let radius = duck_radius();
+    simulation.set_duck_radius(radius);
+
+    let half_extent = 0.5 * simulation.water().extent();
+    for duck in simulation.ducks() {
+        assert_eq!(duck.progress(), 0.0);
+        assert!(duck.path().deboor_points().iter().all(|point| {
+            point.x.abs() <= half_extent.x - radius && point.z.abs() <= half_extent.y - radius
+        }));
+    }
+}
@@ -0,0 +1,62 @@
+use duck::rain::Rain;
+use nalgebra::Vector2;
+use rand::{rngs::StdRng, SeedableRng};
+
+const EXTENT: Vector2<f32> = Vector2::new(10.0, 4.0);
+const DURATION: f32 = 100.0;
+
+fn drop_count(rain: &Rain, steps: usize) -> usize {
+    let mut rng = StdRng::seed_from_u64(5);
+    (0..steps)
+        .map(|_| rain.drops(&mut rng, EXTENT, DURATION / steps as f32).len())
+        .sum()
+}
+
+#[test]
+fn drop_count_follows_intensity_regardless_of_step_length() {
+    let rain = Rain {
+        intensity: 0.5,
+        ..Rain::default()
+    };
+    let expected = rain.intensity * EXTENT.x * EXTENT.y * DURATION;
+
+    for steps in [1, 100, 24000] {
+        let count = drop_count(&rain, steps) as f32;
+        // Five standard deviations of the Poisson distribution
+        assert!(
+            (count - expected).abs() < 5.0 * expected.sqrt(),
+            "{} drops in {} steps, expected {}",
+            count,
+            steps,
+            expected
+        );
+    }
+}
+
+#[test]
+fn drops_fall_inside_the_extent_with_their_radius_range() {
+    let rain = Rain {
+        intensity: 2.0,
+        min_radius: 0.1,
+        max_radius: 0.3,
+        amplitude: -0.25,
+    };
+    let drops = rain.drops(&mut StdRng::seed_from_u64(1), EXTENT, 1.0);
+
+    assert!(!drops.is_empty());
+    for drop in drops {
+        assert!(drop.x.abs() <= 0.5 * EXTENT.x && drop.z.abs() <= 0.5 * EXTENT.y);
+        assert!((0.1..0.3).contains(&drop.radius));
+        assert_eq!(drop.amplitude, -0.25);
+    }
+}
+
+#[test]
+fn no_drops_fall_without_rain() {
+    let rain = Rain {
+        intensity: 0.0,
+        ..Rain::default()
+    };
+
+    assert_eq!(drop_count(&rain, 100), 0);
+}
@@ -0,0 +1,244 @@
+use duck::{
+    duck::Duck,
+    land_mask::LandMask,
+    math::geometry::bezier::BezierBSpline,
+    path_planner::PathCurve,
+    primitives::color::Color,
+    simulation::{Simulation, WaterEvent},
+};
+use nalgebra::Point3;
+use std::time::Duration;
+
+const STEPS: usize = 60;
+const DELTA: Duration = Duration::from_millis(16);
+const PATH_SEGMENTS: usize = 20;
+
+fn heights_after_steps(seed: u64) -> Vec<f32> {
+    let mut simulation = Simulation::new(seed);
+
+    for _ in 0..STEPS {
+        simulation.step(DELTA);
+    }
+
+    simulation.water().heights().to_vec()
+}
+
+fn without_ducks(seed: u64) -> Simulation {
+    let mut simulation = Simulation::new(seed);
+    while !simulation.ducks().is_empty() {
+        simulation.remove_duck(0);
+    }
+
+    simulation
+}
+
+fn bits(heights: &[f32]) -> Vec<u32> {
+    heights.iter().map(|h| h.to_bits()).collect()
+}
+
+#[test]
+fn same_seed_gives_identical_heights() {
+    let first = heights_after_steps(42);
+    let second = heights_after_steps(42);
+
+    assert_eq!(bits(&first), bits(&second));
+}
+
+#[test]
+fn same_seed_and_deltas_give_identical_ducks_and_water() {
+    let run = || {
+        let mut simulation = Simulation::new(7);
+        for _ in 0..STEPS {
+            simulation.step(DELTA);
+        }
+        simulation
+    };
+    let (first, second) = (run(), run());
+
+    assert_eq!(
+        bits(first.water().heights()),
+        bits(second.water().heights())
+    );
+    assert_eq!(first.ducks().len(), second.ducks().len());
+    for (first, second) in first.ducks().iter().zip(second.ducks()) {
+        assert_eq!(bits(first.mtx().as_slice()), bits(second.mtx().as_slice()));
+    }
+}
+
+#[test]
+fn different_seeds_give_different_heights() {
+    let first = heights_after_steps(1);
+    let second = heights_after_steps(2);
+
+    assert_ne!(bits(&first), bits(&second));
+}
+
+#[test]
+fn recorded_water_events_replay_to_same_heights() {
+    // The buoyant duck reacts to the heights, which are not integrated while recording
+    let mut integrated = Simulation::new(42);
+    integrated.duck_buoyancy = false;
+
+    let mut recorded = Simulation::new(42);
+    recorded.duck_buoyancy = false;
+    recorded.record_water_events();
+    let mut water = recorded.water().clone();
+
+    for _ in 0..STEPS {
+        integrated.step(DELTA);
+        recorded.step(DELTA);
+
+        for event in recorded.take_water_events() {
+            match event {
+                WaterEvent::Stamp(disturbance) => water.stamp(&disturbance),
+                WaterEvent::Update => water.update().unwrap(),
+            }
+        }
+    }
+
+    assert_eq!(bits(water.heights()), bits(integrated.water().heights()));
+}
+
+#[test]
+fn duck_path_avoids_land() {
+    let mut water = Simulation::default_water();
+    let (width, depth) = (water.width(), water.depth());
+
+    // Island in the middle of the pool
+    let land = (0..width * depth)
+        .map(|idx| {
+            let (x, y) = (idx % width, idx / width);
+            (width / 4..3 * width / 4).contains(&x) && (depth / 4..3 * depth / 4).contains(&y)
+        })
+        .collect();
+    water.set_land_mask(LandMask::new(width, depth, land));
+
+    let mut simulation = Simulation::with_water(7, water);
+
+    for _ in 0..PATH_SEGMENTS {
+        for duck in simulation.ducks() {
+            for i in 0..=100 {
+                let position = duck.path().value(i as f32 / 100.0);
+                assert!(!simulation.water().is_land_at(position.x, position.z));
+            }
+        }
+
+        // Three meters of travel take the ducks onto new path segments now and then
+        simulation.step(Duration::from_secs(1));
+    }
+}
+
+#[test]
+fn ducks_on_the_same_path_steer_apart() {
+    let path = BezierBSpline::through_points(
+        [-3.0, -1.0, 1.0, 3.0]
+            .into_iter()
+            .map(|x| Point3::new(x, -2.7, 0.0))
+            .collect(),
+    );
+
+    let mut simulation = without_ducks(3);
+    simulation.push_duck(Duck::new(path.clone(), Color::white()));
+    simulation.push_duck(Duck::new(path, Color::orange()));
+
+    for _ in 0..STEPS {
+        simulation.step(DELTA);
+    }
+
+    let ducks = simulation.ducks();
+    let distance = (ducks[0].position() - ducks[1].position()).norm();
+    // The push fades out towards the separation radius while the ducks are drawn back to their
+    // path, so they settle a little inside it
+    assert!(
+        distance > 0.75 * Duck::SEPARATION_RADIUS,
+        "distance {}",
+        distance
+    );
+}
+
+#[test]
+fn duck_moves_at_its_speed_in_meters_per_second() {
+    // Straight path of length 2 along the x axis
+    let path = BezierBSpline::through_points(
+        [-3.0, -1.0, 1.0, 3.0]
+            .into_iter()
+            .map(|x| Point3::new(x, -2.7, 0.0))
+            .collect(),
+    );
+
+    let mut simulation = without_ducks(3);
+    let mut duck = Duck::new(path, Color::white());
+    duck.speed = 1.5;
+    simulation.push_duck(duck);
+
+    let start = simulation.ducks()[0].position();
+    for _ in 0..STEPS {
+        simulation.step(DELTA);
+    }
+
+    let distance = (simulation.ducks()[0].position() - start).norm();
+    let expected = 1.5 * STEPS as f32 * DELTA.as_secs_f32();
+    assert!(
+        (distance - expected).abs() < 1.0e-3,
+        "distance {}",
+        distance
+    );
+}
+
+#[test]
+fn duck_moves_at_its_speed_on_every_curve() {
+    // Straight path of length 2 along the x axis, whichever the curve
+    let points: Vec<_> = [-3.0, -1.0, 1.0, 3.0]
+        .into_iter()
+        .map(|x| Point3::new(x, -2.7, 0.0))
+        .collect();
+    let curves = [
+        PathCurve::BSpline,
+        PathCurve::CatmullRom,
+        PathCurve::Tcb {
+            tension: 0.5,
+            continuity: 0.0,
+            bias: 0.0,
+        },
+    ];
+
+    for curve in curves {
+        let mut simulation = without_ducks(3);
+        let mut duck = Duck::with_curve(points.clone(), curve, Color::white());
+        duck.speed = 1.5;
+        simulation.push_duck(duck);
+
+        let start = simulation.ducks()[0].position();
+        for _ in 0..STEPS {
+            simulation.step(DELTA);
+        }
+
+        let distance = (simulation.ducks()[0].position() - start).norm();
+        let expected = 1.5 * STEPS as f32 * DELTA.as_secs_f32();
+        assert!(
+            (distance - expected).abs() < 1.0e-3,
+            "{:?}: distance {}",
+            curve,
+            distance
+        );
+    }
+}
+
+#[test]
+fn duck_water_rows_cover_every_duck() {
+    let mut simulation = Simulation::new(3);
+    for _ in 0..STEPS {
+        simulation.step(DELTA);
+    }
+
+    let rows = simulation.duck_water_rows();
+    assert!(rows.windows(2).all(|pair| pair[0].end < pair[1].start));
+
+    let water = simulation.water();
+    for duck in simulation.ducks() {
+        let row = water.grid_position(0.0, duck.position().y).y.round() as usize;
+        assert!(rows.iter().any(|rows| rows.contains(&row)));
+    }
+
+    assert!(without_ducks(3).duck_water_rows().is_empty());
+}
@@ -0,0 +1,9 @@
+use duck::utils;
+
+#[test]
+fn normal_components_map_to_full_byte_range() {
+    assert_eq!(utils::normal_f32_to_u8(-1.0), 0);
+    assert_eq!(utils::normal_f32_to_u8(-0.5), 64);
+    assert_eq!(utils::normal_f32_to_u8(0.0), 128);
+    assert_eq!(utils::normal_f32_to_u8(1.0), 255);
+}
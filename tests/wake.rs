@@ -0,0 +1,63 @@
+use duck::{math::geometry::bezier::BezierBSpline, wake::WakeEmitter};
+use nalgebra::Point3;
+
+const SPACING: f32 = 0.1;
+const FRAMES: usize = 100;
+/// Ends the track between two emissions
+const END: f32 = 0.975;
+
+/// Straight path along the x axis from -1 to 1
+fn straight_path() -> BezierBSpline {
+    BezierBSpline::through_points(
+        [-3.0, -1.0, 1.0, 3.0]
+            .into_iter()
+            .map(|x| Point3::new(x, 0.0, 0.0))
+            .collect(),
+    )
+}
+
+fn emitter() -> WakeEmitter {
+    WakeEmitter::new(SPACING, 0.2, 0.01, 0.5)
+}
+
+#[test]
+fn wake_is_evenly_spaced_regardless_of_frame_length() {
+    let path = straight_path();
+
+    let single = emitter().emit(&path, 0.0..END, 1.0);
+
+    let mut wake = emitter();
+    let split: Vec<_> = (0..FRAMES)
+        .flat_map(|i| {
+            let frame = END / FRAMES as f32;
+            wake.emit(&path, i as f32 * frame..(i + 1) as f32 * frame, 1.0)
+        })
+        .collect();
+
+    // A bow and a stern disturbance every spacing along a track of length 1.95
+    assert_eq!(single.len(), 2 * 19);
+    assert_eq!(split.len(), single.len());
+
+    for (a, b) in single.iter().zip(&split) {
+        assert!((a.x - b.x).abs() < 1.0e-3 && a.z == b.z, "{:?} {:?}", a, b);
+    }
+
+    for pair in single.chunks(2).collect::<Vec<_>>().windows(2) {
+        assert!((pair[1][0].x - pair[0][0].x - SPACING).abs() < 1.0e-4);
+    }
+}
+
+#[test]
+fn wake_moves_water_from_stern_to_bow() {
+    let path = straight_path();
+    let weak = emitter().emit(&path, 0.0..0.5, 1.0);
+    let strong = emitter().emit(&path, 0.0..0.5, 50.0);
+
+    for (pair, strong_pair) in weak.chunks(2).zip(strong.chunks(2)) {
+        let (bow, stern) = (pair[0], pair[1]);
+        assert!(bow.x > stern.x);
+        assert!(bow.volume() > 0.0);
+        assert!((bow.volume() + stern.volume()).abs() < 1.0e-9);
+        assert!((strong_pair[0].volume() - 50.0 * bow.volume()).abs() < 1.0e-6);
+    }
+}
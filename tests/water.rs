@@ -0,0 +1,409 @@
+use duck::{
+    bathymetry::Bathymetry,
+    land_mask::LandMask,
+    render::texture::Texture,
+    water::Water,
+    water_boundary::{Boundaries, Boundary},
+    water_disturbance::{Disturbance, DisturbanceKernel, DisturbancePatch},
+};
+use nalgebra::{Vector2, Vector3};
+
+const WIDTH: usize = 64;
+const EXTENT: Vector2<f32> = Vector2::new(2.0, 2.0);
+const TIMESTEP: f32 = 1.0 / 60.0;
+const STEPS: usize = 200;
+const BOUNDARY_STEPS: usize = 600;
+
+fn max_abs_height(water: &Water) -> f32 {
+    water
+        .heights()
+        .iter()
+        .fold(0.0, |max, h| f32::max(max, h.abs()))
+}
+
+#[test]
+fn high_wave_speed_is_substepped() {
+    let water = Water::new(WIDTH, WIDTH, EXTENT, 50.0, TIMESTEP);
+
+    assert!(water.courant_number() > Water::MAX_COURANT_NUMBER);
+    assert!(water.substeps().unwrap() > 1);
+}
+
+#[test]
+fn high_wave_speed_stays_bounded() {
+    let mut water = Water::new(WIDTH, WIDTH, EXTENT, 50.0, TIMESTEP);
+    water.disturb(WIDTH as isize / 2, WIDTH as isize / 2, 1.0);
+
+    for _ in 0..STEPS {
+        water.update().unwrap();
+
+        let max = max_abs_height(&water);
+        assert!(max.is_finite());
+        assert!(max <= 1.0, "height {} exceeds the initial disturbance", max);
+    }
+}
+
+#[test]
+fn excessive_wave_speed_is_an_error() {
+    let mut water = Water::new(WIDTH, WIDTH, EXTENT, 1.0e6, TIMESTEP);
+    water.disturb(WIDTH as isize / 2, WIDTH as isize / 2, 1.0);
+    let heights = water.heights().to_vec();
+
+    assert!(water.update().is_err());
+    assert_eq!(heights, water.heights());
+}
+
+#[test]
+fn rectangular_grid_maps_world_corners() {
+    let water = Water::new(100, 20, Vector2::new(30.0, 6.0), 1.0, TIMESTEP);
+
+    let min = water.grid_position(-15.0, -3.0);
+    let max = water.grid_position(15.0, 3.0);
+
+    assert_eq!((min.x, min.y), (0.0, 0.0));
+    assert_eq!((max.x, max.y), (99.0, 19.0));
+    assert_eq!(water.heights().len(), 100 * 20);
+}
+
+fn energy_ratio_after_reflections(boundaries: Boundaries) -> f32 {
+    let mut water = Water::new(WIDTH, WIDTH, EXTENT, 0.5, TIMESTEP);
+    water.damping_rate = 0.0;
+    water.set_boundaries(boundaries);
+
+    // Zero-mean ripple, so that no water is added to the pool
+    let center = WIDTH as isize / 2;
+    let ripple: Vec<_> = (-6..=6)
+        .flat_map(|dx| (-6..=6).map(move |dy| (dx, dy)))
+        .map(|(dx, dy)| {
+            let r2 = (dx * dx + dy * dy) as f32 / 8.0;
+            (dx, dy, (1.0 - r2) * (-r2).exp())
+        })
+        .collect();
+    let mean = ripple.iter().map(|(_, _, h)| h).sum::<f32>() / ripple.len() as f32;
+
+    for (dx, dy, height) in ripple {
+        water.disturb(center + dx, center + dy, height - mean);
+    }
+
+    let initial = water.energy();
+    for _ in 0..BOUNDARY_STEPS {
+        water.update().unwrap();
+    }
+
+    water.energy() / initial
+}
+
+#[test]
+fn reflective_boundaries_conserve_energy() {
+    let ratio = energy_ratio_after_reflections(Boundaries::uniform(Boundary::Reflective));
+    assert!((ratio - 1.0).abs() < 1.0e-2, "energy ratio {}", ratio);
+}
+
+#[test]
+fn fixed_boundaries_conserve_energy() {
+    let ratio = energy_ratio_after_reflections(Boundaries::uniform(Boundary::Fixed));
+    assert!((ratio - 1.0).abs() < 1.0e-2, "energy ratio {}", ratio);
+}
+
+#[test]
+fn periodic_boundaries_conserve_energy() {
+    let ratio = energy_ratio_after_reflections(Boundaries::uniform(Boundary::Periodic));
+    assert!((ratio - 1.0).abs() < 1.0e-2, "energy ratio {}", ratio);
+}
+
+#[test]
+fn absorbing_boundaries_dissipate_energy() {
+    let ratio =
+        energy_ratio_after_reflections(Boundaries::uniform(Boundary::Absorbing { width: 16 }));
+    assert!(ratio < 0.1, "energy ratio {}", ratio);
+}
+
+#[test]
+fn mixed_boundaries_are_selectable_per_edge() {
+    let boundaries = Boundaries {
+        min_x: Boundary::Periodic,
+        max_x: Boundary::Periodic,
+        min_z: Boundary::Fixed,
+        max_z: Boundary::Reflective,
+    };
+
+    let ratio = energy_ratio_after_reflections(boundaries);
+    assert!((ratio - 1.0).abs() < 1.0e-2, "energy ratio {}", ratio);
+}
+
+#[test]
+#[should_panic]
+fn unpaired_periodic_boundary_is_rejected() {
+    let mut water = Water::new(WIDTH, WIDTH, EXTENT, 0.5, TIMESTEP);
+    water.set_boundaries(Boundaries {
+        min_x: Boundary::Periodic,
+        ..Boundaries::uniform(Boundary::Reflective)
+    });
+}
+
+#[test]
+fn land_reflects_waves_and_stays_dry() {
+    let mut water = Water::new(WIDTH, WIDTH, EXTENT, 0.5, TIMESTEP);
+    water.damping_rate = 0.0;
+
+    // Pier along the z axis, a few samples away from the disturbance
+    let land = (0..WIDTH * WIDTH)
+        .map(|idx| (40..44).contains(&(idx % WIDTH)) && idx / WIDTH > 10)
+        .collect();
+    water.set_land_mask(LandMask::new(WIDTH, WIDTH, land));
+
+    water.disturb(32, 32, 1.0);
+    water.disturb(41, 32, 1.0);
+    let initial = water.energy();
+
+    for _ in 0..BOUNDARY_STEPS {
+        water.update().unwrap();
+    }
+
+    let ratio = water.energy() / initial;
+    assert!((ratio - 1.0).abs() < 1.0e-2, "energy ratio {}", ratio);
+    assert_eq!(water.height(41, 32), 0.0);
+    assert!(water.is_land_at(0.32, 0.5));
+    assert!(!water.is_land_at(0.0, 0.0));
+}
+
+#[test]
+fn land_mask_is_loaded_from_bright_pixels() {
+    let mut image = image::GrayImage::new(8, 4);
+    image.put_pixel(6, 1, image::Luma([255]));
+    let texture = Texture {
+        image: image::DynamicImage::ImageLuma8(image),
+    };
+
+    let mask = LandMask::from_texture(&texture, 8, 4);
+
+    assert!(mask.is_land(6, 1));
+    assert!(!mask.is_land(1, 1));
+}
+
+/// Deep left half and a beach with a quarter of the depth on the right half
+fn beach(width: usize, depth: usize) -> Bathymetry {
+    let depths: Vec<_> = (0..width * depth)
+        .map(|idx| if idx % width < width / 2 { 1.0 } else { 0.25 })
+        .collect();
+
+    Bathymetry::from_depths(width, depth, &depths, 0.25)
+}
+
+#[test]
+fn stability_limit_uses_maximum_wave_speed() {
+    let mut water = Water::new(WIDTH, WIDTH, EXTENT, 0.5, TIMESTEP);
+    let uniform = water.courant_number();
+
+    water.set_bathymetry(beach(WIDTH, WIDTH));
+
+    assert_eq!(water.wave_speed_at(0, 0), 1.0);
+    assert_eq!(water.wave_speed_at(WIDTH - 1, 0), 0.5);
+    assert!((water.courant_number() - 2.0 * uniform).abs() < 1.0e-6);
+}
+
+#[test]
+fn varying_depth_conserves_energy() {
+    let mut water = Water::new(WIDTH, WIDTH, EXTENT, 0.5, TIMESTEP);
+    water.damping_rate = 0.0;
+    water.set_bathymetry(beach(WIDTH, WIDTH));
+    water.disturb(16, 32, 1.0);
+    let initial = water.energy();
+
+    for _ in 0..BOUNDARY_STEPS {
+        water.update().unwrap();
+
+        let max = max_abs_height(&water);
+        assert!(max.is_finite() && max <= 1.0);
+    }
+
+    let ratio = water.energy() / initial;
+    assert!((ratio - 1.0).abs() < 1.0e-2, "energy ratio {}", ratio);
+}
+
+#[test]
+fn heightmap_maps_bright_pixels_to_shallow_water() {
+    let mut image = image::GrayImage::new(4, 4);
+    image.put_pixel(3, 0, image::Luma([191]));
+    let texture = Texture {
+        image: image::DynamicImage::ImageLuma8(image),
+    };
+
+    let bathymetry = Bathymetry::from_heightmap(&texture, 4, 4);
+
+    assert_eq!(bathymetry.speed_scale(0, 0), 1.0);
+    assert!((bathymetry.speed_scale(3, 0) - 0.5).abs() < 1.0e-2);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_update_matches_serial() {
+    let run = |parallel: bool| {
+        let mut water = Water::new(WIDTH, 48, EXTENT, 0.5, TIMESTEP);
+        water.parallel = parallel;
+        water.set_boundaries(Boundaries::uniform(Boundary::Absorbing { width: 8 }));
+        water.set_bathymetry(beach(WIDTH, 48));
+        water.disturb(20, 20, 1.0);
+
+        for _ in 0..STEPS {
+            water.update().unwrap();
+        }
+
+        water
+    };
+
+    let serial = run(false);
+    let parallel = run(true);
+
+    assert_eq!(serial.heights(), parallel.heights());
+    assert_eq!(
+        serial.normal_texture().image.as_bytes(),
+        parallel.normal_texture().image.as_bytes()
+    );
+}
+
+#[test]
+fn surface_is_interpolated_between_samples() {
+    // Samples one world unit apart, the first one at -2
+    let mut water = Water::new(5, 5, Vector2::new(4.0, 4.0), 1.0, TIMESTEP);
+    water.disturb(1, 1, 1.0);
+
+    assert_eq!(water.surface_height(-1.0, -1.0), 1.0);
+    assert_eq!(water.surface_height(-0.5, -1.0), 0.5);
+    assert_eq!(water.surface_height(-0.5, -0.5), 0.25);
+    assert_eq!(water.surface_height(5.0, 5.0), 0.0);
+
+    let flat = water.surface_normal(1.5, 1.5);
+    assert!((flat - Vector3::y()).norm() < 1.0e-6);
+
+    // The surface rises towards the disturbance
+    let slope = water.surface_normal(-0.5, -1.0);
+    assert!(slope.x > 0.0 && slope.z.abs() < 1.0e-6, "{:?}", slope);
+}
+
+#[test]
+fn heights_are_interpolated_between_steps() {
+    let mut water = Water::new(WIDTH, WIDTH, EXTENT, 1.0, TIMESTEP);
+    water.disturb(WIDTH as isize / 2, WIDTH as isize / 2, 1.0);
+    water.update().unwrap();
+
+    let mut heights = Vec::new();
+    water.interpolate_heights(0.0, &mut heights);
+    assert_eq!(heights, water.previous_heights());
+
+    water.interpolate_heights(1.0, &mut heights);
+    assert_eq!(heights, water.heights());
+    assert_eq!(
+        water.normal_texture_of(&heights).image.as_rgba32f(),
+        water.normal_texture().image.as_rgba32f()
+    );
+
+    water.interpolate_heights(0.5, &mut heights);
+    for ((height, current), previous) in heights
+        .iter()
+        .zip(water.heights())
+        .zip(water.previous_heights())
+    {
+        assert!((height - 0.5 * (current + previous)).abs() < 1.0e-6);
+    }
+}
+
+const KERNELS: [DisturbanceKernel; 3] = [
+    DisturbanceKernel::Gaussian,
+    DisturbanceKernel::Cosine,
+    DisturbanceKernel::RaisedCosine,
+];
+
+#[test]
+fn disturbance_kernels_fall_off_to_zero_at_the_radius() {
+    for kernel in KERNELS {
+        assert_eq!(kernel.weight(0.0), 1.0, "{:?}", kernel);
+        assert!(kernel.weight(0.999).abs() < 1.0e-2, "{:?}", kernel);
+        assert_eq!(kernel.weight(1.0), 0.0, "{:?}", kernel);
+        assert!(kernel.weight(0.3) > kernel.weight(0.6), "{:?}", kernel);
+    }
+}
+
+#[test]
+fn stamped_disturbance_displaces_its_volume() {
+    for kernel in KERNELS {
+        let mut water = Water::new(WIDTH, WIDTH, EXTENT, 0.5, TIMESTEP);
+        let disturbance = Disturbance::with_volume(0.1, -0.2, 0.5, -0.03, kernel);
+        water.stamp(&disturbance);
+
+        let step = water.derivative_step();
+        let volume: f32 = water.heights().iter().sum::<f32>() * step.x * step.y;
+        assert!(
+            (volume + 0.03).abs() < 1.0e-3,
+            "{:?} volume {}",
+            kernel,
+            volume
+        );
+    }
+}
+
+#[test]
+fn stamped_disturbances_accumulate() {
+    let mut water = Water::new(WIDTH, WIDTH, EXTENT, 0.5, TIMESTEP);
+    water.disturb(32, 32, 0.5);
+    let center = water.world_position(32.0, 32.0);
+    let disturbance = Disturbance::new(
+        center.x,
+        center.y,
+        0.2,
+        0.25,
+        DisturbanceKernel::RaisedCosine,
+    );
+
+    water.stamp(&disturbance);
+    water.stamp(&disturbance);
+
+    assert_eq!(water.height(32, 32), 1.0);
+    assert!(water.height(33, 32) > 0.0 && water.height(33, 32) < 0.5);
+    assert_eq!(water.height(40, 32), 0.0);
+}
+
+#[test]
+fn added_patches_stamp_like_their_disturbances() {
+    let mut stamped = Water::new(WIDTH, WIDTH, EXTENT, 0.5, TIMESTEP);
+    let mut patched = stamped.clone();
+
+    let disturbances = [
+        Disturbance::new(-0.5, -0.4, 0.3, 1.0, DisturbanceKernel::RaisedCosine),
+        Disturbance::new(0.6, 0.2, 0.2, -0.5, DisturbanceKernel::Gaussian),
+        Disturbance::new(-0.4, -0.3, 0.1, 0.25, DisturbanceKernel::RaisedCosine),
+    ];
+
+    let mut patch = DisturbancePatch::default();
+    for disturbance in &disturbances {
+        stamped.stamp(disturbance);
+        patch.add(&stamped.disturbance_patch(disturbance));
+    }
+    patched.add_patch(&patch);
+
+    for (stamped, patched) in stamped.heights().iter().zip(patched.heights()) {
+        assert!(
+            (stamped - patched).abs() < 1.0e-6,
+            "{} != {}",
+            stamped,
+            patched
+        );
+    }
+}
+
+#[test]
+fn stamped_disturbance_is_clipped_at_edges_and_land() {
+    let mut water = Water::new(WIDTH, WIDTH, EXTENT, 0.5, TIMESTEP);
+    let land = (0..WIDTH * WIDTH).map(|idx| idx % WIDTH == 1).collect();
+    water.set_land_mask(LandMask::new(WIDTH, WIDTH, land));
+
+    let corner = water.world_position(0.0, 0.0);
+    let disturbance = Disturbance::new(corner.x, corner.y, 0.1, 1.0, DisturbanceKernel::Gaussian);
+    let patch = water.disturbance_patch(&disturbance);
+    assert_eq!((patch.x.start, patch.y.start), (0, 0));
+
+    water.stamp(&disturbance);
+    assert_eq!(water.height(0, 0), 1.0);
+    assert_eq!(water.height(1, 0), 0.0);
+    assert!(water.height(0, 1) > 0.0);
+}
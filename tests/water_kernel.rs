@@ -0,0 +1,67 @@
+use duck::{
+    bathymetry::Bathymetry,
+    land_mask::LandMask,
+    water::Water,
+    water_boundary::{Boundaries, Boundary},
+};
+use nalgebra::Vector2;
+
+const WIDTH: usize = 64;
+const DEPTH: usize = 48;
+const EXTENT: Vector2<f32> = Vector2::new(2.0, 2.0);
+const TIMESTEP: f32 = 1.0 / 60.0;
+const STEPS: usize = 200;
+
+fn beach(width: usize, depth: usize) -> Bathymetry {
+    let depths: Vec<_> = (0..width * depth)
+        .map(|idx| if idx % width < width / 2 { 1.0 } else { 0.25 })
+        .collect();
+
+    Bathymetry::from_depths(width, depth, &depths, 0.25)
+}
+
+#[test]
+fn optimized_kernel_matches_reference() {
+    let boundaries = [
+        Boundaries::uniform(Boundary::Reflective),
+        Boundaries::uniform(Boundary::Absorbing { width: 8 }),
+        Boundaries {
+            min_x: Boundary::Periodic,
+            max_x: Boundary::Periodic,
+            min_z: Boundary::Fixed,
+            max_z: Boundary::Absorbing { width: 4 },
+        },
+    ];
+
+    for boundaries in boundaries {
+        let run = |reference: bool| {
+            let mut water = Water::new(WIDTH, DEPTH, EXTENT, 0.5, TIMESTEP);
+            water.set_boundaries(boundaries);
+            water.set_bathymetry(beach(WIDTH, DEPTH));
+
+            let land = (0..WIDTH * DEPTH)
+                .map(|idx| (40..44).contains(&(idx % WIDTH)) && idx / WIDTH > 10)
+                .collect();
+            water.set_land_mask(LandMask::new(WIDTH, DEPTH, land));
+
+            water.disturb(20, 20, 1.0);
+            water.disturb(0, 47, -1.0);
+
+            for _ in 0..STEPS {
+                if reference {
+                    water.update_reference().unwrap();
+                } else {
+                    water.update().unwrap();
+                }
+            }
+
+            water
+                .heights()
+                .iter()
+                .map(|h| h.to_bits())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(run(true), run(false), "{:?}", boundaries);
+    }
+}